@@ -6,9 +6,14 @@
     holding buffers for the duration of a data transfer."
 )]
 
-use esp_hal::{clock::CpuClock, delay::Delay, main, time::Rate};
+use esp_hal::{
+    clock::CpuClock,
+    delay::Delay,
+    main,
+    time::{Instant, Rate},
+};
 
-use alloc::boxed::Box;
+use core::time::Duration;
 use embedded_hal_bus::spi::ExclusiveDevice;
 use epd_waveshare::{epd2in9_v2::*, prelude::*};
 use esp_hal::gpio::{Input, InputConfig, Level, Output, OutputConfig, Pull};
@@ -17,6 +22,7 @@ use esp_hal::spi::{
     master::{Config, Spi},
 };
 use mousefood::prelude::*;
+use mousefood::{RefreshPolicy, WaveshareEpd, epd_flush_callback};
 use ratatui::style::Stylize;
 use ratatui::widgets::{Block, Paragraph, Wrap};
 use ratatui::{Frame, Terminal};
@@ -64,16 +70,21 @@ fn main() -> ! {
     let mut spi_device = ExclusiveDevice::new(spi, cs, delay).expect("could not init spi device");
 
     // setup display
-    let mut epd = Epd2in9::new(&mut spi_device, busy, dc, rst, &mut delay, None).unwrap();
-    let mut display = Display2in9::default();
-    display.set_rotation(DisplayRotation::Rotate90);
+    let epd = Epd2in9::new(&mut spi_device, busy, dc, rst, &mut delay, None).unwrap();
+    let mut display = WaveshareEpd::new(
+        epd,
+        Display2in9::default(),
+        spi_device,
+        delay,
+        RefreshPolicy::new().full_every(20),
+    );
+    display
+        .display_mut()
+        .set_rotation(DisplayRotation::Rotate90);
 
     // setup mousefood
     let backend = EmbeddedBackendConfig {
-        flush_callback: Box::new(move |display: &mut Display2in9| {
-            epd.update_and_display_frame(&mut spi_device, display.buffer(), &mut delay)
-                .unwrap();
-        }),
+        flush_callback: epd_flush_callback(uptime),
         ..Default::default()
     };
 
@@ -90,3 +101,8 @@ fn draw(frame: &mut Frame) {
     let bordered_block = Block::bordered().title("Mousefood");
     frame.render_widget(paragraph.block(bordered_block), frame.area());
 }
+
+/// Returns the time since boot.
+fn uptime() -> Duration {
+    Duration::from_micros(Instant::now().duration_since_epoch().as_micros())
+}
@@ -45,8 +45,9 @@ fn main() -> Result<(), Error> {
         flush_callback: Box::new(move |display| {
             simulator_window.update(display);
             if simulator_window.events().any(|e| e == SimulatorEvent::Quit) {
-                panic!("simulator window closed");
+                return Err("simulator window closed".into());
             }
+            Ok(())
         }),
         ..Default::default()
     };
@@ -58,7 +59,12 @@ fn main() -> Result<(), Error> {
 
     // Run an infinite loop, where widgets will be rendered
     loop {
-        terminal.draw(draw)?;
+        match terminal.draw(draw) {
+            Ok(_) => {}
+            // The flush callback fails once the window is closed
+            Err(Error::FlushError(_)) => return Ok(()),
+            Err(error) => return Err(error),
+        }
     }
 }
 
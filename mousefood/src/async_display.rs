@@ -0,0 +1,37 @@
+//! Displays receiving the framebuffer asynchronously.
+//!
+//! Async display drivers, e.g. on top of `embedded-hal-async` SPI, send the framebuffer with
+//! [`EmbeddedBackend::flush_async`](crate::EmbeddedBackend::flush_async), so the executor keeps
+//! running other tasks during the transfer.
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::primitives::Rectangle;
+
+/// A display whose driver can send pixels asynchronously.
+///
+/// The [`DrawTarget`] implementation is only used by the backend for the size of the display
+/// when the framebuffer is sent with
+/// [`EmbeddedBackend::flush_async`](crate::EmbeddedBackend::flush_async).
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// impl AsyncDisplay for MyDisplay {
+///     async fn write_pixels(&mut self, area: Rectangle, pixels: &[Rgb565]) -> Result<(), Self::Error> {
+///         self.set_address_window(area).await?;
+///         self.spi.write(bytemuck::cast_slice(pixels)).await
+///     }
+/// }
+/// ```
+// Like `embedded-hal-async`, leave `Send` bounds of the futures to the executor.
+#[allow(async_fn_in_trait)]
+pub trait AsyncDisplay: DrawTarget {
+    /// Sends `pixels` to `area` of the display, row by row.
+    ///
+    /// `pixels` holds exactly one color for every point of `area`.
+    async fn write_pixels(
+        &mut self,
+        area: Rectangle,
+        pixels: &[Self::Color],
+    ) -> Result<(), Self::Error>;
+}
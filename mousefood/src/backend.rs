@@ -1,13 +1,27 @@
 use alloc::boxed::Box;
 use core::marker::PhantomData;
 
+#[cfg(feature = "framebuffer")]
+use crate::async_display::AsyncDisplay;
+use crate::cell::{CellColors, CellTarget, PixelRole};
+use crate::color_filter::ColorFilter;
+use crate::color_mapper::{ColorMapper, DefaultColorMapper};
 use crate::colors::*;
 use crate::default_font;
+use crate::dither::Dithering;
+use crate::error::{DrawTargetError, Error, FlushError};
+#[cfg(feature = "framebuffer")]
+use crate::framebuffer::{FramebufferLayout, FramebufferMemory};
+use crate::native_palette::NativePalette;
+#[cfg(feature = "framebuffer")]
+use crate::rectangle::envelope;
+use crate::transform::{Transform, Transformed};
 use embedded_graphics::Drawable;
 use embedded_graphics::draw_target::DrawTarget;
 use embedded_graphics::geometry::{self, Dimensions};
 use embedded_graphics::mono_font::{MonoFont, MonoTextStyleBuilder};
-use embedded_graphics::pixelcolor::{PixelColor, Rgb888};
+use embedded_graphics::pixelcolor::PixelColor;
+use embedded_graphics::primitives::Rectangle;
 use embedded_graphics::text::Text;
 use ratatui_core::backend::{Backend, ClearType};
 use ratatui_core::layout;
@@ -24,14 +38,33 @@ pub enum TerminalAlignment {
     End,
 }
 
+/// Display rotation, applied clockwise.
+#[derive(Clone, Copy)]
+pub enum Rotation {
+    /// No rotation.
+    Rotate0,
+    /// Rotation by 90 degrees.
+    Rotate90,
+    /// Rotation by 180 degrees.
+    Rotate180,
+    /// Rotation by 270 degrees.
+    Rotate270,
+}
+
+/// Callback fired after each buffer flush, see [`EmbeddedBackendConfig::flush_callback`].
+pub type FlushCallback<D> = Box<dyn FnMut(&mut D) -> Result<(), FlushError>>;
+
 /// Embedded backend configuration.
 pub struct EmbeddedBackendConfig<D, C>
 where
     D: DrawTarget<Color = C>,
-    C: PixelColor,
+    C: PixelColor + 'static,
 {
     /// Callback fired after each buffer flush.
-    pub flush_callback: Box<dyn FnMut(&mut D)>,
+    ///
+    /// An error returned by the callback is returned from [`Backend::flush`] as
+    /// [`Error::FlushError`](crate::error::Error::FlushError).
+    pub flush_callback: FlushCallback<D>,
     /// Regular font.
     pub font_regular: MonoFont<'static>,
     /// Bold font.
@@ -49,26 +82,146 @@ where
 
     /// Color theme that maps Ratatui colors to display pixels.
     pub color_theme: ColorTheme,
+
+    /// Rotates the terminal on the display.
+    ///
+    /// Useful for display drivers and draw targets that can't rotate on their own.
+    pub rotation: Rotation,
+
+    /// Mirrors the terminal horizontally (left to right), before applying the rotation.
+    pub mirror_horizontal: bool,
+
+    /// Mirrors the terminal vertically (top to bottom), before applying the rotation.
+    pub mirror_vertical: bool,
+
+    /// Part of the display occupied by the terminal, in display coordinates.
+    ///
+    /// The rest of the display is left untouched and can be drawn by the application.
+    /// If `None`, the terminal occupies the whole display.
+    pub viewport: Option<Rectangle>,
+
+    /// Dithers colors which the display can't show.
+    ///
+    /// Applies to monochrome (`BinaryColor`), `Gray2`, `Gray4` and EPD displays.
+    /// If `None`, colors are mapped to the nearest display color.
+    pub dithering: Option<Dithering>,
+
+    /// Keeps text legible when the foreground and background colors of a cell map to the same
+    /// display color, e.g. `Color::Gray` on `Color::White` on a monochrome display.
+    ///
+    /// If `true`, such a foreground is replaced with black or white, whichever differs from the
+    /// background on the display.
+    pub contrast_guard: bool,
+
+    /// Maps Ratatui colors to display colors.
+    ///
+    /// Use an [`InkPalette`](crate::InkPalette) for displays with a few colors whose inks differ
+    /// from the defaults, or a custom [`ColorMapper`] for other pixel color types.
+    pub color_mapper: Box<dyn ColorMapper<C>>,
+
+    /// Adjusts colors after the theme resolved them, e.g. for a night mode.
+    ///
+    /// Can be changed at runtime with [`EmbeddedBackend::set_color_filter`].
+    pub color_filter: ColorFilter,
+
+    /// Leaves sending the framebuffer to [`EmbeddedBackend::flush_async`], or to the application,
+    /// e.g. with [`EmbeddedBackend::front_buffer_bytes`].
+    ///
+    /// If `true`, [`Backend::flush`] (e.g. at the end of `Terminal::draw`) neither draws the
+    /// framebuffer to the display nor calls the [`flush_callback`](Self::flush_callback).
+    #[cfg(feature = "framebuffer")]
+    pub async_flush: bool,
+
+    /// Allocates a second framebuffer, see [`EmbeddedBackend::swap_buffers`].
+    ///
+    /// The backend draws into the back buffer while the front buffer holds the last complete
+    /// frame, e.g. to stream it to the display with DMA while the next frame is drawn.
+    #[cfg(feature = "framebuffer")]
+    pub double_buffer: bool,
+
+    /// How the framebuffer stores its pixels, e.g. packed into the bytes sent to the display, or
+    /// as indices into a palette to save memory.
+    ///
+    /// Defaults to [`FramebufferLayout::native`], a `C` for every pixel.
+    #[cfg(feature = "framebuffer")]
+    pub framebuffer_layout: FramebufferLayout<C>,
+
+    /// Memory for the framebuffer, instead of allocating it on the heap.
+    ///
+    /// Useful to place the framebuffer in a linker section, e.g. in PSRAM or in DMA-capable RAM,
+    /// and to know its size at link time. It must hold the
+    /// [`memory_len`](FramebufferLayout::memory_len) of the
+    /// [`framebuffer_layout`](Self::framebuffer_layout) for the display (or the
+    /// [`viewport`](Self::viewport)), twice with [`double_buffer`](Self::double_buffer), or only
+    /// for a band with [`band_rows`](Self::band_rows). [`FramebufferMemory::Pixels`] holds the
+    /// native layout, [`FramebufferMemory::Bytes`] the others.
+    #[cfg(feature = "framebuffer")]
+    pub framebuffer_memory: Option<FramebufferMemory<C>>,
+
+    /// Renders the terminal in horizontal bands of this many cell rows, for displays larger
+    /// than the available memory.
+    ///
+    /// The framebuffer only holds a band. The backend keeps the cells of the frame, and
+    /// [`Backend::flush`] rasterizes them band by band, sending each band to the display with
+    /// a single `fill_contiguous`. The whole terminal is sent on every flush.
+    ///
+    /// Must not be `Some(0)`, and can't be combined with [`async_flush`](Self::async_flush) and
+    /// [`double_buffer`](Self::double_buffer): [`EmbeddedBackend::new`] panics in both cases.
+    #[cfg(feature = "framebuffer")]
+    pub band_rows: Option<u16>,
 }
 
-impl<D, C> Default for EmbeddedBackendConfig<D, C>
+impl<D, C> EmbeddedBackendConfig<D, C>
 where
     D: DrawTarget<Color = C>,
-    C: PixelColor,
+    C: PixelColor + 'static,
 {
-    fn default() -> Self {
+    /// Creates a default configuration with a custom color mapper.
+    ///
+    /// Useful for pixel color types which [`DefaultColorMapper`] doesn't support,
+    /// as [`Default`] is only implemented for the supported ones.
+    pub fn with_color_mapper(color_mapper: impl ColorMapper<C> + 'static) -> Self {
         Self {
-            flush_callback: Box::new(|_| {}),
+            flush_callback: Box::new(|_| Ok(())),
             font_regular: default_font::get_regular(),
             font_bold: None,
             font_italic: None,
             vertical_alignment: TerminalAlignment::Start,
             horizontal_alignment: TerminalAlignment::Start,
             color_theme: ColorTheme::default(),
+            rotation: Rotation::Rotate0,
+            mirror_horizontal: false,
+            mirror_vertical: false,
+            viewport: None,
+            dithering: None,
+            contrast_guard: false,
+            color_mapper: Box::new(color_mapper),
+            color_filter: ColorFilter::new(),
+            #[cfg(feature = "framebuffer")]
+            async_flush: false,
+            #[cfg(feature = "framebuffer")]
+            double_buffer: false,
+            #[cfg(feature = "framebuffer")]
+            framebuffer_layout: FramebufferLayout::native(),
+            #[cfg(feature = "framebuffer")]
+            framebuffer_memory: None,
+            #[cfg(feature = "framebuffer")]
+            band_rows: None,
         }
     }
 }
 
+impl<D, C> Default for EmbeddedBackendConfig<D, C>
+where
+    D: DrawTarget<Color = C>,
+    C: PixelColor + 'static,
+    DefaultColorMapper: ColorMapper<C>,
+{
+    fn default() -> Self {
+        Self::with_color_mapper(DefaultColorMapper)
+    }
+}
+
 /// Embedded backend for Ratatui.
 ///
 /// # Examples
@@ -97,21 +250,43 @@ where
 pub struct EmbeddedBackend<'display, D, C>
 where
     D: DrawTarget<Color = C> + 'display,
-    C: PixelColor + 'display,
+    C: PixelColor + 'static,
 {
     display: &'display mut D,
     display_type: PhantomData<D>,
 
-    flush_callback: Box<dyn FnMut(&mut D)>,
+    flush_callback: FlushCallback<D>,
 
     #[cfg(feature = "framebuffer")]
     buffer: crate::framebuffer::HeapBuffer<C>,
+    #[cfg(feature = "framebuffer")]
+    async_flush: bool,
+    #[cfg(feature = "framebuffer")]
+    front_buffer: Option<crate::framebuffer::HeapBuffer<C>>,
+    /// Area of the back buffer drawn since the last swap, in display coordinates.
+    #[cfg(feature = "framebuffer")]
+    dirty_area: Option<Rectangle>,
+    #[cfg(feature = "framebuffer")]
+    bands: Option<Bands>,
+
+    /// Cells drawn by Ratatui, to render the whole terminal again.
+    cells: ratatui_core::buffer::Buffer,
+    /// Whether the next flush renders every cell again, e.g. after the theme changed.
+    redraw: bool,
 
     font_regular: MonoFont<'static>,
     font_bold: Option<MonoFont<'static>>,
     font_italic: Option<MonoFont<'static>>,
+    vertical_alignment: TerminalAlignment,
+    horizontal_alignment: TerminalAlignment,
 
     char_offset: geometry::Point,
+    transform: Transform,
+    dithering: Option<Dithering>,
+    contrast_guard: bool,
+    color_mapper: Box<dyn ColorMapper<C>>,
+    color_filter: ColorFilter,
+    palette: NativePalette<C>,
 
     columns_rows: layout::Size,
     pixels: layout::Size,
@@ -121,7 +296,7 @@ where
 impl<'display, D, C> EmbeddedBackend<'display, D, C>
 where
     D: DrawTarget<Color = C> + Dimensions + 'static,
-    C: PixelColor + Into<Rgb888> + From<Rgb888> + for<'a> From<TermColor<'a>> + 'static,
+    C: PixelColor + 'static,
 {
     fn init(
         display: &'display mut D,
@@ -135,48 +310,155 @@ where
             vertical_alignment,
             horizontal_alignment,
             color_theme,
+            rotation,
+            mirror_horizontal,
+            mirror_vertical,
+            viewport,
+            dithering,
+            contrast_guard,
+            color_mapper,
+            color_filter,
+            #[cfg(feature = "framebuffer")]
+            async_flush,
+            #[cfg(feature = "framebuffer")]
+            double_buffer,
+            #[cfg(feature = "framebuffer")]
+            framebuffer_layout,
+            #[cfg(feature = "framebuffer")]
+            framebuffer_memory,
+            #[cfg(feature = "framebuffer")]
+            band_rows,
         } = config;
+        let area = match viewport {
+            Some(viewport) => display.bounding_box().intersection(&viewport),
+            None => display.bounding_box(),
+        };
+        let transform = Transform::new(area, rotation, mirror_horizontal, mirror_vertical);
         let pixels = layout::Size {
-            width: display.bounding_box().size.width as u16,
-            height: display.bounding_box().size.height as u16,
+            width: transform.size().width as u16,
+            height: transform.size().height as u16,
         };
 
-        let extra_x = pixels.width % font_regular.character_size.width as u16;
-        let extra_y = pixels.height % font_regular.character_size.height as u16;
-
-        let off_x = match horizontal_alignment {
-            TerminalAlignment::Start => 0,
-            TerminalAlignment::Center => extra_x / 2, //best effort, might be 1/2 pixel off
-            TerminalAlignment::End => extra_x,
-        } as i32;
-        let off_y = match vertical_alignment {
-            TerminalAlignment::Start => 0,
-            TerminalAlignment::Center => extra_y / 2, //best effort, might be 1/2 pixel off
-            TerminalAlignment::End => extra_y,
-        } as i32;
+        let palette = NativePalette::new(|color, color_type| {
+            map_color(
+                color,
+                color_type,
+                &color_theme,
+                &color_filter,
+                color_mapper.as_ref(),
+            )
+        });
 
-        let char_offset = geometry::Point::new(off_x, off_y);
+        #[cfg(feature = "framebuffer")]
+        let bands = band_rows.map(|rows| {
+            assert!(rows > 0, "band_rows must not be 0");
+            assert!(
+                !async_flush && !double_buffer,
+                "band_rows can't be combined with async_flush or double_buffer"
+            );
+            Bands {
+                rows,
+                height: (u32::from(rows) * font_regular.character_size.height)
+                    .min(transform.size().height),
+            }
+        });
+        #[cfg(feature = "framebuffer")]
+        let (buffer, front_buffer) = new_framebuffers(
+            match &bands {
+                Some(bands) => transform.map_rectangle(&Rectangle::new(
+                    geometry::Point::zero(),
+                    geometry::Size::new(transform.size().width, bands.height),
+                )),
+                None => area,
+            },
+            map_color(
+                style::Color::Reset,
+                TermColorType::Background,
+                &color_theme,
+                &color_filter,
+                color_mapper.as_ref(),
+            ),
+            framebuffer_layout,
+            framebuffer_memory,
+            double_buffer,
+        );
 
-        Self {
+        let mut backend = Self {
             #[cfg(feature = "framebuffer")]
-            buffer: crate::framebuffer::HeapBuffer::new(display.bounding_box(), color_theme),
+            buffer,
+            #[cfg(feature = "framebuffer")]
+            front_buffer,
+            #[cfg(feature = "framebuffer")]
+            async_flush,
+            #[cfg(feature = "framebuffer")]
+            dirty_area: None,
+            #[cfg(feature = "framebuffer")]
+            bands,
+            cells: ratatui_core::buffer::Buffer::default(),
+            redraw: false,
             display,
             display_type: PhantomData,
             flush_callback: Box::new(flush_callback),
             font_regular,
             font_bold,
             font_italic,
-            char_offset,
-            columns_rows: layout::Size {
-                height: pixels.height / font_regular.character_size.height as u16,
-                width: pixels.width / font_regular.character_size.width as u16,
-            },
+            vertical_alignment,
+            horizontal_alignment,
+            char_offset: geometry::Point::zero(),
+            transform,
+            dithering,
+            contrast_guard,
+            color_mapper,
+            color_filter,
+            palette,
+            columns_rows: layout::Size::default(),
             pixels,
             color_theme,
-        }
+        };
+        backend.update_grid();
+        #[cfg(feature = "framebuffer")]
+        backend.reserve_palette_colors();
+        backend
+    }
+
+    /// Recomputes the character grid from the regular font and the alignment.
+    fn update_grid(&mut self) {
+        let char_size = self.font_regular.character_size;
+        let extra_x = self.pixels.width % char_size.width as u16;
+        let extra_y = self.pixels.height % char_size.height as u16;
+
+        let off_x = match self.horizontal_alignment {
+            TerminalAlignment::Start => 0,
+            TerminalAlignment::Center => extra_x / 2, //best effort, might be 1/2 pixel off
+            TerminalAlignment::End => extra_x,
+        } as i32;
+        let off_y = match self.vertical_alignment {
+            TerminalAlignment::Start => 0,
+            TerminalAlignment::Center => extra_y / 2, //best effort, might be 1/2 pixel off
+            TerminalAlignment::End => extra_y,
+        } as i32;
+
+        self.char_offset = geometry::Point::new(off_x, off_y);
+        self.columns_rows = layout::Size {
+            height: self.pixels.height / char_size.height as u16,
+            width: self.pixels.width / char_size.width as u16,
+        };
+        self.cells.resize(layout::Rect::from((
+            layout::Position::ORIGIN,
+            self.columns_rows,
+        )));
     }
 
     /// Creates a new `EmbeddedBackend` using default fonts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`framebuffer_memory`](EmbeddedBackendConfig::framebuffer_memory) is too small
+    /// for the framebuffer.
+    ///
+    /// Panics if [`band_rows`](EmbeddedBackendConfig::band_rows) is `Some(0)`, or is combined
+    /// with [`async_flush`](EmbeddedBackendConfig::async_flush) or
+    /// [`double_buffer`](EmbeddedBackendConfig::double_buffer).
     pub fn new(
         display: &'display mut D,
         config: EmbeddedBackendConfig<D, C>,
@@ -193,97 +475,582 @@ where
     pub fn display_mut(&mut self) -> &mut D {
         self.display
     }
+
+    /// Replaces the fonts, e.g. for a large text mode.
+    ///
+    /// The size of the terminal is recomputed from the regular font. Ratatui resizes the terminal
+    /// and redraws it on the next draw if the size changed; otherwise the next flush renders
+    /// every cell again with the new fonts.
+    ///
+    /// With [`band_rows`](EmbeddedBackendConfig::band_rows), the bands are resized to the new
+    /// font height, which may need a larger framebuffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FramebufferMemoryTooSmall`] if the bands of the new fonts don't fit into
+    /// [`framebuffer_memory`](EmbeddedBackendConfig::framebuffer_memory). The fonts are then
+    /// left unchanged.
+    pub fn set_fonts(
+        &mut self,
+        font_regular: MonoFont<'static>,
+        font_bold: Option<MonoFont<'static>>,
+        font_italic: Option<MonoFont<'static>>,
+    ) -> Result<(), Error<D::Error>> {
+        #[cfg(feature = "framebuffer")]
+        self.resize_bands(font_regular.character_size.height)?;
+        self.font_regular = font_regular;
+        self.font_bold = font_bold;
+        self.font_italic = font_italic;
+        self.update_grid();
+        self.redraw = true;
+        Ok(())
+    }
+
+    /// Changes how the view is aligned when the display size is not an exact multiple of the
+    /// font size.
+    ///
+    /// The next flush renders every cell again at the new position.
+    pub fn set_alignment(
+        &mut self,
+        horizontal_alignment: TerminalAlignment,
+        vertical_alignment: TerminalAlignment,
+    ) {
+        self.horizontal_alignment = horizontal_alignment;
+        self.vertical_alignment = vertical_alignment;
+        self.update_grid();
+        self.redraw = true;
+    }
+
+    /// Resizes the bands of [`band_rows`](EmbeddedBackendConfig::band_rows) to characters
+    /// `char_height` pixels high.
+    #[cfg(feature = "framebuffer")]
+    fn resize_bands(&mut self, char_height: u32) -> Result<(), Error<D::Error>> {
+        let background = self.background();
+        let Some(bands) = &mut self.bands else {
+            return Ok(());
+        };
+        let size = self.transform.size();
+        let height = (u32::from(bands.rows) * char_height).min(size.height);
+        let area = self.transform.map_rectangle(&Rectangle::new(
+            geometry::Point::zero(),
+            geometry::Size::new(size.width, height),
+        ));
+        let needed = self.buffer.memory_len(area.size);
+        self.buffer
+            .resize(area, background)
+            .map_err(|available| Error::FramebufferMemoryTooSmall { needed, available })?;
+        bands.height = height;
+        Ok(())
+    }
+
+    /// Returns the color theme.
+    pub fn color_theme(&self) -> &ColorTheme {
+        &self.color_theme
+    }
+
+    /// Replaces the color theme, e.g. to toggle between a light and a dark theme.
+    ///
+    /// The next flush, e.g. at the end of the next `Terminal::draw`, renders every cell again
+    /// with the new theme. With [`double_buffer`](EmbeddedBackendConfig::double_buffer), the
+    /// front buffer isn't modified until the next swap.
+    pub fn set_color_theme(&mut self, color_theme: ColorTheme) {
+        self.color_theme = color_theme;
+        self.update_palette();
+        self.redraw = true;
+    }
+
+    /// Returns the color filter.
+    pub fn color_filter(&self) -> &ColorFilter {
+        &self.color_filter
+    }
+
+    /// Replaces the color filter, e.g. to switch to a night mode.
+    ///
+    /// The next flush renders every cell again with the new filter, as with
+    /// [`set_color_theme`](Self::set_color_theme).
+    pub fn set_color_filter(&mut self, color_filter: ColorFilter) {
+        self.color_filter = color_filter;
+        self.update_palette();
+        self.redraw = true;
+    }
+
+    /// Makes the frame drawn into the back buffer the front buffer,
+    /// with [`double_buffer`](EmbeddedBackendConfig::double_buffer).
+    ///
+    /// The parts of the frame drawn since the previous swap are copied forward into the new back
+    /// buffer, as Ratatui only draws the cells that changed. The front buffer isn't modified
+    /// until the next swap, so a DMA transfer can read it while the next frame is drawn.
+    ///
+    /// [`Backend::flush`] and [`flush_async`](Self::flush_async) swap the buffers before
+    /// sending the front buffer. Without double buffering, this does nothing.
+    #[cfg(feature = "framebuffer")]
+    pub fn swap_buffers(&mut self) {
+        let Some(front_buffer) = &mut self.front_buffer else {
+            return;
+        };
+        core::mem::swap(&mut self.buffer, front_buffer);
+        if let Some(dirty_area) = self.dirty_area.take() {
+            self.buffer.copy_area_from(front_buffer, &dirty_area);
+        }
+    }
+
+    /// Returns the pixels of the front buffer in row-major order,
+    /// or of the framebuffer without [`double_buffer`](EmbeddedBackendConfig::double_buffer).
+    ///
+    /// The pixels cover [`framebuffer_area`](Self::framebuffer_area). Returns `None` unless the
+    /// [`framebuffer_layout`](EmbeddedBackendConfig::framebuffer_layout) is
+    /// [`native`](FramebufferLayout::native).
+    ///
+    /// # Transfers from the front buffer
+    ///
+    /// A transfer (e.g. DMA) may keep reading the memory of the returned slice after the borrow
+    /// ends, while `Terminal::draw` renders the next frame, as long as:
+    ///
+    /// - [`double_buffer`](EmbeddedBackendConfig::double_buffer) is enabled; otherwise every draw
+    ///   writes into this memory,
+    /// - the transfer is finished before the next [`swap_buffers`](Self::swap_buffers), which
+    ///   also happens in [`Backend::flush`] and [`flush_async`](Self::flush_async): the memory
+    ///   then becomes the back buffer,
+    /// - the transfer is finished before the backend is dropped, which frees the memory unless it
+    ///   is [`framebuffer_memory`](EmbeddedBackendConfig::framebuffer_memory).
+    #[cfg(feature = "framebuffer")]
+    pub fn front_buffer(&self) -> Option<&[C]> {
+        self.front_buffer.as_ref().unwrap_or(&self.buffer).pixels()
+    }
+
+    /// Returns the bytes of the front buffer, e.g. to send them to the display over SPI,
+    /// with the [`packed`](FramebufferLayout::packed)
+    /// [`framebuffer_layout`](EmbeddedBackendConfig::framebuffer_layout).
+    ///
+    /// Returns `None` with the other layouts. Transfers from the returned slice follow the rules
+    /// of [`front_buffer`](Self::front_buffer#transfers-from-the-front-buffer).
+    #[cfg(feature = "framebuffer")]
+    pub fn front_buffer_bytes(&self) -> Option<&[u8]> {
+        self.front_buffer
+            .as_ref()
+            .unwrap_or(&self.buffer)
+            .as_bytes()
+    }
+
+    /// Returns the number of pixels drawn with the closest color of the palette since the
+    /// backend was created, as the palette of the [indexed](FramebufferLayout::indexed)
+    /// [`framebuffer_layout`](EmbeddedBackendConfig::framebuffer_layout) was full.
+    ///
+    /// Always `0` with the other layouts. A growing count means that frames show more colors
+    /// than the palette holds, e.g. [`IndexBits::Eight`](crate::IndexBits::Eight) is needed.
+    #[cfg(feature = "framebuffer")]
+    pub fn palette_overflows(&self) -> usize {
+        self.buffer.palette_overflows()
+            + self
+                .front_buffer
+                .as_ref()
+                .map_or(0, |front_buffer| front_buffer.palette_overflows())
+    }
+
+    /// Returns the part of the display covered by the framebuffer, in display coordinates.
+    #[cfg(feature = "framebuffer")]
+    pub fn framebuffer_area(&self) -> Rectangle {
+        self.buffer.bounding_box()
+    }
+
+    /// Returns the display color of the default background.
+    fn background(&self) -> C {
+        map_color(
+            style::Color::Reset,
+            TermColorType::Background,
+            &self.color_theme,
+            &self.color_filter,
+            self.color_mapper.as_ref(),
+        )
+    }
+
+    /// Recomputes the palette after the theme or the filter changed.
+    fn update_palette(&mut self) {
+        self.palette = NativePalette::new(|color, color_type| {
+            map_color(
+                color,
+                color_type,
+                &self.color_theme,
+                &self.color_filter,
+                self.color_mapper.as_ref(),
+            )
+        });
+        #[cfg(feature = "framebuffer")]
+        self.reserve_palette_colors();
+    }
+
+    /// Reserves entries for the named colors in the palettes of the
+    /// [indexed](FramebufferLayout::indexed) framebuffers.
+    #[cfg(feature = "framebuffer")]
+    fn reserve_palette_colors(&mut self) {
+        self.buffer.reserve_colors(self.palette.named_colors());
+        if let Some(front_buffer) = &mut self.front_buffer {
+            front_buffer.reserve_colors(self.palette.named_colors());
+        }
+    }
+}
+
+#[cfg(feature = "framebuffer")]
+impl<D, C> EmbeddedBackend<'_, D, C>
+where
+    D: AsyncDisplay<Color = C> + 'static,
+    C: PixelColor + 'static,
+{
+    /// Sends the framebuffer to the display with [`AsyncDisplay::write_pixels`].
+    ///
+    /// Set [`EmbeddedBackendConfig::async_flush`] so that `Terminal::draw` only renders into the
+    /// framebuffer, then await this method after each draw. The
+    /// [`flush_callback`](EmbeddedBackendConfig::flush_callback) isn't called.
+    ///
+    /// Without the [`native`](FramebufferLayout::native)
+    /// [`framebuffer_layout`](EmbeddedBackendConfig::framebuffer_layout), the pixels are sent
+    /// row by row, each converted to colors on the heap.
+    pub async fn flush_async(&mut self) -> Result<(), Error<D::Error>> {
+        self.swap_buffers();
+        let front_buffer = self.front_buffer.as_ref().unwrap_or(&self.buffer);
+        let area = front_buffer.bounding_box();
+        if let Some(pixels) = front_buffer.pixels() {
+            return self
+                .display
+                .write_pixels(area, pixels)
+                .await
+                .map_err(|error| Error::DrawError(DrawTargetError(error)));
+        }
+        let width = area.size.width as usize;
+        let mut colors = front_buffer.into_iter();
+        let mut row = alloc::vec::Vec::with_capacity(width);
+        for y in area.rows() {
+            row.clear();
+            row.extend(colors.by_ref().take(width));
+            let row_area = Rectangle::new(
+                geometry::Point::new(area.top_left.x, y),
+                geometry::Size::new(area.size.width, 1),
+            );
+            self.display
+                .write_pixels(row_area, &row)
+                .await
+                .map_err(|error| Error::DrawError(DrawTargetError(error)))?;
+        }
+        Ok(())
+    }
 }
 
-type Result<T, E = crate::error::Error> = core::result::Result<T, E>;
+/// Creates the framebuffer and, with double buffering, the front buffer.
+///
+/// # Panics
+///
+/// Panics if `memory` is too small for the buffers, or doesn't match `layout`.
+#[cfg(feature = "framebuffer")]
+fn new_framebuffers<C: PixelColor>(
+    area: Rectangle,
+    background: C,
+    layout: FramebufferLayout<C>,
+    memory: Option<FramebufferMemory<C>>,
+    double_buffer: bool,
+) -> (
+    crate::framebuffer::HeapBuffer<C>,
+    Option<crate::framebuffer::HeapBuffer<C>>,
+) {
+    use crate::framebuffer::HeapBuffer;
 
-impl<D, C> Backend for EmbeddedBackend<'_, D, C>
+    let Some(memory) = memory else {
+        let buffer = HeapBuffer::with_layout(area, background, layout, None);
+        let front_buffer =
+            double_buffer.then(|| HeapBuffer::with_layout(area, background, layout, None));
+        return (buffer, front_buffer);
+    };
+    layout.assert_memory(&memory);
+    let len = layout.memory_len(area.size);
+    let needed = if double_buffer { 2 * len } else { len };
+    assert!(
+        memory.len() >= needed,
+        "framebuffer_memory holds {} elements, {needed} are needed",
+        memory.len()
+    );
+    let (memory, front_memory) = if double_buffer {
+        let (memory, front_memory) = memory.split_at(len);
+        (memory, Some(front_memory))
+    } else {
+        (memory, None)
+    };
+    let buffer = HeapBuffer::with_layout(area, background, layout, Some(memory));
+    let front_buffer =
+        front_memory.map(|memory| HeapBuffer::with_layout(area, background, layout, Some(memory)));
+    (buffer, front_buffer)
+}
+
+/// Maps a terminal color to a display color through the filter, the theme and the color mapper.
+fn map_color<C: PixelColor>(
+    color: style::Color,
+    color_type: TermColorType,
+    theme: &ColorTheme,
+    color_filter: &ColorFilter,
+    color_mapper: &dyn ColorMapper<C>,
+) -> C {
+    let color = color_filter.filter(color, color_type, theme);
+    color_mapper.map(color, color_type, theme)
+}
+
+/// Converts an error of the target drawn by the backend.
+#[cfg(feature = "framebuffer")]
+fn draw_error<E>(error: core::convert::Infallible) -> Error<E> {
+    match error {}
+}
+
+/// Converts an error of the target drawn by the backend.
+#[cfg(not(feature = "framebuffer"))]
+fn draw_error<E>(error: E) -> Error<E> {
+    Error::DrawError(DrawTargetError(error))
+}
+
+impl<D, C> EmbeddedBackend<'_, D, C>
 where
     D: DrawTarget<Color = C> + 'static,
-    C: PixelColor + Into<Rgb888> + From<Rgb888> + for<'a> From<TermColor<'a>> + 'static,
+    D::Error: core::fmt::Debug,
+    C: PixelColor + 'static,
 {
-    type Error = crate::error::Error;
-
-    fn draw<'a, I>(&mut self, content: I) -> Result<()>
-    where
-        I: Iterator<Item = (u16, u16, &'a ratatui_core::buffer::Cell)>,
-    {
-        for (x, y, cell) in content {
-            let position = geometry::Point::new(
-                x as i32 * self.font_regular.character_size.width as i32,
-                y as i32 * self.font_regular.character_size.height as i32,
-            );
+    /// Draws the cell at column `x` and row `y` into the framebuffer, or to the display.
+    fn draw_cell(
+        &mut self,
+        x: u16,
+        y: u16,
+        cell: &ratatui_core::buffer::Cell,
+    ) -> Result<(), Error<D::Error>> {
+        let position = geometry::Point::new(
+            x as i32 * self.font_regular.character_size.width as i32,
+            y as i32 * self.font_regular.character_size.height as i32,
+        );
 
-            let mut style_builder = MonoTextStyleBuilder::new()
-                .font(&self.font_regular)
-                .text_color(
-                    TermColor::new(cell.fg, TermColorType::Foreground, &self.color_theme).into(),
-                )
-                .background_color(
-                    TermColor::new(cell.bg, TermColorType::Background, &self.color_theme).into(),
-                );
+        let mut style_builder = MonoTextStyleBuilder::new()
+            .font(&self.font_regular)
+            .text_color(PixelRole::Foreground)
+            .background_color(PixelRole::Background);
 
-            for modifier in cell.modifier.iter() {
-                style_builder = match modifier {
-                    style::Modifier::BOLD => match &self.font_bold {
-                        None => style_builder,
-                        Some(font) => style_builder.font(font),
-                    },
-                    style::Modifier::DIM => style_builder, // TODO
-                    style::Modifier::ITALIC => match &self.font_italic {
-                        None => style_builder,
-                        Some(font) => style_builder.font(font),
-                    },
-                    style::Modifier::UNDERLINED => style_builder.underline(),
-                    style::Modifier::SLOW_BLINK => style_builder, // TODO
-                    style::Modifier::RAPID_BLINK => style_builder, // TODO
-                    style::Modifier::REVERSED => style_builder,   // TODO
-                    style::Modifier::HIDDEN => style_builder,     // TODO
-                    style::Modifier::CROSSED_OUT => style_builder.strikethrough(),
-                    _ => style_builder,
-                }
+        for modifier in cell.modifier.iter() {
+            style_builder = match modifier {
+                style::Modifier::BOLD => match &self.font_bold {
+                    None => style_builder,
+                    Some(font) => style_builder.font(font),
+                },
+                style::Modifier::DIM => style_builder, // TODO
+                style::Modifier::ITALIC => match &self.font_italic {
+                    None => style_builder,
+                    Some(font) => style_builder.font(font),
+                },
+                style::Modifier::UNDERLINED => style_builder.underline(),
+                style::Modifier::SLOW_BLINK => style_builder, // TODO
+                style::Modifier::RAPID_BLINK => style_builder, // TODO
+                style::Modifier::REVERSED => style_builder,   // TODO
+                style::Modifier::HIDDEN => style_builder,     // TODO
+                style::Modifier::CROSSED_OUT => style_builder.strikethrough(),
+                _ => style_builder,
             }
+        }
 
-            #[cfg(feature = "underline-color")]
-            if cell.underline_color != style::Color::Reset {
-                style_builder = style_builder.underline_with_color(
-                    TermColor::new(
-                        cell.underline_color,
-                        TermColorType::Foreground,
-                        &self.color_theme,
-                    )
-                    .into(),
-                );
-            }
+        #[cfg_attr(not(feature = "underline-color"), allow(unused_mut))]
+        let mut underline = cell.fg;
+        #[cfg(feature = "underline-color")]
+        if cell.underline_color != style::Color::Reset {
+            style_builder = style_builder.underline_with_color(PixelRole::Underline);
+            underline = cell.underline_color;
+        }
+        let colors = CellColors {
+            background: cell.bg,
+            foreground: cell.fg,
+            underline,
+        };
+        let resolved = colors.resolve(|color, color_type| {
+            self.palette.get_or_insert_with(color, color_type, || {
+                map_color(
+                    color,
+                    color_type,
+                    &self.color_theme,
+                    &self.color_filter,
+                    self.color_mapper.as_ref(),
+                )
+            })
+        });
+        // dithering maps every pixel on its own, from the filtered colors
+        let colors = match self.dithering {
+            None => colors,
+            Some(_) => colors.map(|color, color_type| {
+                self.color_filter
+                    .filter(color, color_type, &self.color_theme)
+            }),
+        };
 
-            Text::with_baseline(
-                cell.symbol(),
-                position + self.char_offset,
-                style_builder.build(),
-                embedded_graphics::text::Baseline::Top,
-            )
-            .draw(
+        let text = Text::with_baseline(
+            cell.symbol(),
+            position + self.char_offset,
+            style_builder.build(),
+            embedded_graphics::text::Baseline::Top,
+        );
+        #[cfg(feature = "framebuffer")]
+        if self.front_buffer.is_some() {
+            let area = self.transform.map_rectangle(&text.bounding_box());
+            self.dirty_area = Some(match self.dirty_area {
+                Some(dirty_area) => envelope(&dirty_area, &area),
+                None => area,
+            });
+        }
+        text.draw(&mut CellTarget::new(
+            &mut Transformed::new(
                 #[cfg(feature = "framebuffer")]
                 &mut self.buffer,
                 #[cfg(not(feature = "framebuffer"))]
                 self.display,
-            )
-            .map_err(|_| crate::error::Error::DrawError)?;
+                self.transform,
+            ),
+            colors,
+            resolved,
+            &self.color_theme,
+            self.color_mapper.as_ref(),
+            self.dithering,
+            self.contrast_guard,
+        ))
+        .map_err(draw_error)?;
+        Ok(())
+    }
+
+    /// Draws every cell of the rows `rows` of `cells`.
+    fn draw_rows(
+        &mut self,
+        cells: &ratatui_core::buffer::Buffer,
+        rows: core::ops::Range<u16>,
+    ) -> Result<(), Error<D::Error>> {
+        for y in rows {
+            for x in 0..cells.area.width {
+                self.draw_cell(x, y, &cells[(x, y)])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fills the framebuffer with the background, and marks it to be sent by the next flush.
+    #[cfg(feature = "framebuffer")]
+    fn clear_target(&mut self) -> Result<(), Error<D::Error>> {
+        let background = self.background();
+        self.dirty_area = Some(self.buffer.bounding_box());
+        self.buffer.clear(background).map_err(draw_error)
+    }
+
+    /// Fills the display with the background.
+    #[cfg(not(feature = "framebuffer"))]
+    fn clear_target(&mut self) -> Result<(), Error<D::Error>> {
+        let background = self.background();
+        Transformed::new(self.display, self.transform)
+            .clear(background)
+            .map_err(draw_error)
+    }
+
+    /// Renders `cells` again if needed, and sends the framebuffer to the display.
+    fn render(&mut self, cells: &ratatui_core::buffer::Buffer) -> Result<(), Error<D::Error>> {
+        #[cfg(feature = "framebuffer")]
+        if let Some(bands) = self.bands.take() {
+            self.redraw = false;
+            let result = self.draw_bands(&bands, cells);
+            self.bands = Some(bands);
+            return result;
+        }
+        if self.redraw {
+            self.redraw = false;
+            self.clear_target()?;
+            self.draw_rows(cells, 0..cells.area.height)?;
+        }
+        #[cfg(feature = "framebuffer")]
+        if !self.async_flush {
+            self.swap_buffers();
+            let front_buffer = self.front_buffer.as_ref().unwrap_or(&self.buffer);
+            self.display
+                .fill_contiguous(&front_buffer.bounding_box(), front_buffer)
+                .map_err(|error| Error::DrawError(DrawTargetError(error)))?;
+        }
+        Ok(())
+    }
+
+    /// Rasterizes `cells` band by band, sending each band to the display.
+    #[cfg(feature = "framebuffer")]
+    fn draw_bands(
+        &mut self,
+        bands: &Bands,
+        cells: &ratatui_core::buffer::Buffer,
+    ) -> Result<(), Error<D::Error>> {
+        let background = self.background();
+        let width = self.transform.size().width;
+        let height = self.transform.size().height;
+        let char_height = self.font_regular.character_size.height as i32;
+        for top in (0..height).step_by(bands.height as usize) {
+            let band = Rectangle::new(
+                geometry::Point::new(0, top as i32),
+                geometry::Size::new(width, bands.height.min(height - top)),
+            );
+            let area = self.transform.map_rectangle(&band);
+            self.buffer.set_bounding_box(area);
+            self.buffer.clear(background).map_err(draw_error)?;
+
+            // rows of cells overlapping the band
+            let first_row = (band.top_left.y - self.char_offset.y).div_euclid(char_height);
+            let last_row = (band.top_left.y + band.size.height as i32 - 1 - self.char_offset.y)
+                .div_euclid(char_height);
+            let rows = first_row.max(0)..(last_row + 1).min(i32::from(cells.area.height));
+            self.draw_rows(cells, rows.start as u16..rows.end as u16)?;
+
+            self.display
+                .fill_contiguous(&area, &self.buffer)
+                .map_err(|error| Error::DrawError(DrawTargetError(error)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Bands rendering the frame with [`EmbeddedBackendConfig::band_rows`].
+#[cfg(feature = "framebuffer")]
+struct Bands {
+    /// Number of cell rows of a band.
+    rows: u16,
+    /// Height of a band in pixels, before the rotation.
+    height: u32,
+}
+
+impl<D, C> Backend for EmbeddedBackend<'_, D, C>
+where
+    D: DrawTarget<Color = C> + 'static,
+    D::Error: core::fmt::Debug,
+    C: PixelColor + 'static,
+{
+    type Error = Error<D::Error>;
+
+    fn draw<'a, I>(&mut self, content: I) -> Result<(), Self::Error>
+    where
+        I: Iterator<Item = (u16, u16, &'a ratatui_core::buffer::Cell)>,
+    {
+        // bands and redraws render the stored cells on the next flush
+        #[cfg(feature = "framebuffer")]
+        let deferred = self.redraw || self.bands.is_some();
+        #[cfg(not(feature = "framebuffer"))]
+        let deferred = self.redraw;
+        for (x, y, cell) in content {
+            if let Some(stored) = self.cells.cell_mut((x, y)) {
+                *stored = cell.clone();
+            }
+            if !deferred {
+                self.draw_cell(x, y, cell)?;
+            }
         }
         Ok(())
     }
 
-    fn hide_cursor(&mut self) -> Result<()> {
+    fn hide_cursor(&mut self) -> Result<(), Self::Error> {
         // TODO
         Ok(())
     }
 
-    fn show_cursor(&mut self) -> Result<()> {
+    fn show_cursor(&mut self) -> Result<(), Self::Error> {
         // TODO
         Ok(())
     }
 
-    fn get_cursor_position(&mut self) -> Result<layout::Position> {
+    fn get_cursor_position(&mut self) -> Result<layout::Position, Self::Error> {
         // TODO
         Ok(layout::Position::new(0, 0))
     }
@@ -291,80 +1058,66 @@ where
     fn set_cursor_position<P: Into<layout::Position>>(
         &mut self,
         #[allow(unused_variables)] position: P,
-    ) -> Result<()> {
+    ) -> Result<(), Self::Error> {
         // TODO
         Ok(())
     }
 
-    #[cfg(feature = "framebuffer")]
-    fn clear(&mut self) -> Result<()> {
-        self.buffer
-            .clear(
-                TermColor::new(
-                    style::Color::Reset,
-                    TermColorType::Background,
-                    &self.color_theme,
-                )
-                .into(),
-            )
-            .map_err(|_| crate::error::Error::DrawError)
-    }
-
-    #[cfg(not(feature = "framebuffer"))]
-    fn clear(&mut self) -> Result<()> {
-        self.display
-            .clear(
-                TermColor::new(
-                    style::Color::Reset,
-                    TermColorType::Background,
-                    &self.color_theme,
-                )
-                .into(),
-            )
-            .map_err(|_| crate::error::Error::DrawError)
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.cells.reset();
+        self.redraw = false;
+        self.clear_target()
     }
 
-    fn clear_region(&mut self, clear_type: ClearType) -> Result<()> {
+    fn clear_region(&mut self, clear_type: ClearType) -> Result<(), Self::Error> {
         match clear_type {
             ClearType::All => self.clear(),
             ClearType::AfterCursor
             | ClearType::BeforeCursor
             | ClearType::CurrentLine
-            | ClearType::UntilNewLine => Err(crate::error::Error::ClearTypeUnsupported(
-                alloc::format!("{:?}", clear_type),
-            )),
+            | ClearType::UntilNewLine => Err(Error::ClearTypeUnsupported(alloc::format!(
+                "{:?}", clear_type
+            ))),
         }
     }
 
-    fn size(&self) -> Result<layout::Size> {
+    fn size(&self) -> Result<layout::Size, Self::Error> {
         Ok(self.columns_rows)
     }
 
-    fn window_size(&mut self) -> Result<ratatui_core::backend::WindowSize> {
+    fn window_size(&mut self) -> Result<ratatui_core::backend::WindowSize, Self::Error> {
         Ok(ratatui_core::backend::WindowSize {
             columns_rows: self.columns_rows,
             pixels: self.pixels,
         })
     }
 
-    fn flush(&mut self) -> Result<()> {
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        let cells = core::mem::take(&mut self.cells);
+        let result = self.render(&cells);
+        self.cells = cells;
+        result?;
         #[cfg(feature = "framebuffer")]
-        self.display
-            .fill_contiguous(&self.display.bounding_box(), &self.buffer)
-            .map_err(|_| crate::error::Error::DrawError)?;
-        (self.flush_callback)(self.display);
-        Ok(())
+        if self.async_flush {
+            return Ok(());
+        }
+        (self.flush_callback)(self.display).map_err(Error::FlushError)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "framebuffer")]
+    use crate::IndexBits;
     use {
         super::*,
         embedded_graphics::{
             mock_display::MockDisplay,
-            mono_font::{MonoTextStyle, ascii::FONT_4X6},
-            pixelcolor::{Rgb888, RgbColor},
+            mono_font::{
+                MonoTextStyle,
+                ascii::{FONT_4X6, FONT_6X10},
+            },
+            pixelcolor::{BinaryColor, Rgb888, RgbColor},
             prelude::*,
             text::{Alignment, LineHeight, Text, TextStyleBuilder},
         },
@@ -444,4 +1197,1151 @@ mod tests {
 
         display0.assert_eq(&display1);
     }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn renders_grayscale() {
+        use embedded_graphics::pixelcolor::{Gray4, GrayColor};
+
+        let mut display = MockDisplay::<Gray4>::new();
+        display.set_allow_overdraw(true);
+        let config = EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(EmbeddedBackend::new(&mut display, config)).unwrap();
+        terminal
+            .draw(|frame| {
+                use ratatui::style::Stylize;
+                frame.render_widget("T".on_dark_gray(), frame.area());
+            })
+            .unwrap();
+
+        let display = terminal.backend().display();
+        // stem of the "T" glyph on the dark gray background
+        assert_eq!(display.get_pixel(Point::new(1, 1)), Some(Gray4::WHITE));
+        assert_eq!(display.get_pixel(Point::new(0, 1)), Some(Gray4::new(0xa)));
+        assert_eq!(display.get_pixel(Point::new(4, 0)), Some(Gray4::BLACK));
+    }
+
+    #[rstest]
+    #[case(None, ["..", ".."])]
+    #[case(Some(Dithering::Bayer2x2), ["#.", ".#"])]
+    fn renders_dithered(#[case] dithering: Option<Dithering>, #[case] expected: [&str; 2]) {
+        let mut display = MockDisplay::<BinaryColor>::new();
+        display.set_allow_overdraw(true);
+        let config = EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            dithering,
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(EmbeddedBackend::new(&mut display, config)).unwrap();
+        terminal
+            .draw(|frame| {
+                use ratatui::style::{Color, Stylize};
+                frame.render_widget(" ".bg(Color::Rgb(128, 128, 128)), frame.area());
+            })
+            .unwrap();
+
+        let display = terminal.backend().display();
+        for (y, row) in expected.iter().enumerate() {
+            for (x, pixel) in row.chars().enumerate() {
+                let point = Point::new(x as i32, y as i32);
+                let expected = if pixel == '#' {
+                    BinaryColor::On
+                } else {
+                    BinaryColor::Off
+                };
+                assert_eq!(display.get_pixel(point), Some(expected), "pixel {point:?}");
+                // the pattern repeats every 2 pixels
+                assert_eq!(display.get_pixel(point + Point::new(2, 2)), Some(expected));
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(Box::new(DefaultColorMapper), BinaryColor::Off)]
+    #[case(Box::new(crate::InkPalette::<BinaryColor>::DEFAULT), BinaryColor::On)]
+    fn renders_with_color_mapper(
+        #[case] color_mapper: Box<dyn ColorMapper<BinaryColor>>,
+        #[case] expected: BinaryColor,
+    ) {
+        let mut display = MockDisplay::<BinaryColor>::new();
+        display.set_allow_overdraw(true);
+        let config = EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            color_mapper,
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(EmbeddedBackend::new(&mut display, config)).unwrap();
+        terminal
+            .draw(|frame| {
+                use ratatui::style::Stylize;
+                frame.render_widget(" ".on_light_yellow(), frame.area());
+            })
+            .unwrap();
+
+        assert_eq!(
+            terminal.backend().display().get_pixel(Point::zero()),
+            Some(expected)
+        );
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn renders_custom_pixel_color() {
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        struct Led(u8);
+
+        impl PixelColor for Led {
+            type Raw = ();
+        }
+
+        struct LedMapper;
+
+        impl ColorMapper<Led> for LedMapper {
+            fn map(&self, color: style::Color, _: TermColorType, _: &ColorTheme) -> Led {
+                match color {
+                    style::Color::Red => Led(2),
+                    style::Color::Reset => Led(0),
+                    _ => Led(1),
+                }
+            }
+        }
+
+        let mut display = MockDisplay::<Led>::new();
+        display.set_allow_overdraw(true);
+        let config = EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            ..EmbeddedBackendConfig::with_color_mapper(LedMapper)
+        };
+        let mut terminal = Terminal::new(EmbeddedBackend::new(&mut display, config)).unwrap();
+        terminal
+            .draw(|frame| {
+                use ratatui::style::Stylize;
+                frame.render_widget("T".red().on_blue(), frame.area());
+            })
+            .unwrap();
+
+        let display = terminal.backend().display();
+        // stem of the "T" glyph
+        assert_eq!(display.get_pixel(Point::new(1, 1)), Some(Led(2)));
+        assert_eq!(display.get_pixel(Point::new(0, 1)), Some(Led(1)));
+        assert_eq!(display.get_pixel(Point::new(4, 0)), Some(Led(0)));
+    }
+
+    #[rstest]
+    fn changes_color_filter_at_runtime(mut display0: MockDisplay<Rgb888>) {
+        let config = EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            color_filter: ColorFilter::night_mode(),
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(EmbeddedBackend::new(&mut display0, config)).unwrap();
+        let draw = |terminal: &mut Terminal<EmbeddedBackend<_, _>>| {
+            terminal
+                .draw(|frame| frame.render_widget("T", frame.area()))
+                .unwrap();
+        };
+        draw(&mut terminal);
+        // stem of the "T" glyph and the background below it
+        let pixels = |terminal: &Terminal<EmbeddedBackend<MockDisplay<Rgb888>, _>>| {
+            let display = terminal.backend().display();
+            (
+                display.get_pixel(Point::new(1, 1)),
+                display.get_pixel(Point::new(1, 5)),
+            )
+        };
+        assert_eq!(
+            pixels(&terminal),
+            (Some(Rgb888::new(255, 128, 0)), Some(Rgb888::BLACK))
+        );
+
+        terminal
+            .backend_mut()
+            .set_color_filter(ColorFilter::inverted());
+        draw(&mut terminal);
+        assert_eq!(
+            pixels(&terminal),
+            (Some(Rgb888::BLACK), Some(Rgb888::WHITE))
+        );
+    }
+
+    #[rstest]
+    fn changes_color_theme_at_runtime(mut display0: MockDisplay<Rgb888>) {
+        let config = EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(EmbeddedBackend::new(&mut display0, config)).unwrap();
+        let draw = |terminal: &mut Terminal<EmbeddedBackend<_, _>>| {
+            terminal
+                .draw(|frame| frame.render_widget("T", frame.area()))
+                .unwrap();
+        };
+        // stem of the "T" glyph and the background below it
+        let pixels = |terminal: &Terminal<EmbeddedBackend<MockDisplay<Rgb888>, _>>| {
+            let display = terminal.backend().display();
+            (
+                display.get_pixel(Point::new(1, 1)),
+                display.get_pixel(Point::new(1, 5)),
+            )
+        };
+        draw(&mut terminal);
+        let ansi = ColorTheme::ansi();
+        assert_eq!(
+            pixels(&terminal),
+            (Some(ansi.foreground), Some(ansi.background))
+        );
+
+        let light = ColorTheme {
+            foreground: Rgb888::BLACK,
+            background: Rgb888::WHITE,
+            ..ColorTheme::ansi()
+        };
+        terminal.backend_mut().set_color_theme(light);
+        assert_eq!(terminal.backend().color_theme().background, Rgb888::WHITE);
+        draw(&mut terminal);
+        assert_eq!(
+            pixels(&terminal),
+            (Some(Rgb888::BLACK), Some(Rgb888::WHITE))
+        );
+    }
+
+    #[rstest]
+    fn repaints_whole_terminal_after_theme_change(
+        mut display0: MockDisplay<Rgb888>,
+        mut display1: MockDisplay<Rgb888>,
+    ) {
+        use ratatui::style::Stylize;
+        use ratatui::text::Line;
+        let draw = |frame: &mut ratatui::Frame| {
+            let line = Line::from_iter(["Theme".red().on_blue(), " test".into()]);
+            frame.render_widget(line, frame.area());
+        };
+        let theme = ColorTheme::tokyo_night();
+        let config = |color_theme| EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            color_theme,
+            ..Default::default()
+        };
+        {
+            let backend = EmbeddedBackend::new(&mut display0, config(theme));
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal.clear().expect("to clear");
+            terminal.draw(draw).expect("to draw");
+        }
+
+        let backend = EmbeddedBackend::new(&mut display1, config(ColorTheme::ansi()));
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal.draw(draw).expect("to draw");
+        terminal.backend_mut().set_color_theme(theme);
+        terminal.draw(draw).expect("to draw");
+
+        terminal.backend().display().assert_eq(&display0);
+    }
+
+    #[rstest]
+    fn changes_fonts_at_runtime(mut display0: MockDisplay<Rgb888>) {
+        let config = EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(EmbeddedBackend::new(&mut display0, config)).unwrap();
+        assert_eq!(terminal.size().unwrap(), layout::Size::new(16, 10));
+
+        terminal
+            .backend_mut()
+            .set_fonts(FONT_6X10, Some(FONT_6X10), None)
+            .unwrap();
+        assert_eq!(terminal.size().unwrap(), layout::Size::new(10, 6));
+        let frame = terminal
+            .draw(|frame| frame.render_widget("T", frame.area()))
+            .unwrap();
+        assert_eq!(frame.area, layout::Rect::new(0, 0, 10, 6));
+    }
+
+    #[rstest]
+    #[case(TerminalAlignment::Start, TerminalAlignment::Start, Point::new(0, 0))]
+    #[case(TerminalAlignment::Center, TerminalAlignment::Center, Point::new(2, 2))]
+    #[case(TerminalAlignment::End, TerminalAlignment::Start, Point::new(4, 0))]
+    fn changes_alignment_at_runtime(
+        mut display0: MockDisplay<Rgb888>,
+        #[case] horizontal: TerminalAlignment,
+        #[case] vertical: TerminalAlignment,
+        #[case] expected: Point,
+    ) {
+        let mut backend = EmbeddedBackend::new(&mut display0, Default::default());
+        backend.set_fonts(FONT_6X10, None, None).unwrap();
+        backend.set_alignment(horizontal, vertical);
+        assert_eq!(backend.char_offset, expected);
+        assert_eq!(backend.size().unwrap(), layout::Size::new(10, 6));
+    }
+
+    #[rstest]
+    fn returns_flush_callback_error(mut display0: MockDisplay<Rgb888>) {
+        let config = EmbeddedBackendConfig {
+            flush_callback: Box::new(|_| Err("display disconnected".into())),
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display0, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+
+        let error = terminal.draw(|_| {}).unwrap_err();
+        let Error::FlushError(source) = error else {
+            panic!("expected a flush error, got {error:?}");
+        };
+        assert_eq!(alloc::format!("{source}"), "display disconnected");
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum BusError {
+        Timeout,
+    }
+
+    struct FailingDisplay;
+
+    impl DrawTarget for FailingDisplay {
+        type Color = Rgb888;
+        type Error = BusError;
+
+        fn draw_iter<I>(&mut self, _pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            Err(BusError::Timeout)
+        }
+    }
+
+    impl OriginDimensions for FailingDisplay {
+        fn size(&self) -> Size {
+            Size::new(64, 64)
+        }
+    }
+
+    #[rstest]
+    fn returns_display_error() {
+        let mut display = FailingDisplay;
+        let backend = EmbeddedBackend::new(&mut display, Default::default());
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+
+        let error = terminal
+            .draw(|frame| frame.render_widget("Test", frame.area()))
+            .unwrap_err();
+        let Error::DrawError(DrawTargetError(driver_error)) = &error else {
+            panic!("expected a draw error, got {error:?}");
+        };
+        assert_eq!(*driver_error, BusError::Timeout);
+
+        let source = core::error::Error::source(&error).expect("to have a source");
+        assert_eq!(alloc::format!("{source}"), "Timeout");
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn swaps_double_buffers(mut display0: MockDisplay<Rgb888>, mut display1: MockDisplay<Rgb888>) {
+        let text = |text: &'static str| {
+            move |frame: &mut ratatui::Frame| frame.render_widget(text, frame.area())
+        };
+        let backend = EmbeddedBackend::new(&mut display0, Default::default());
+        let mut reference = Terminal::new(backend).expect("to create terminal");
+        let background = reference.backend().front_buffer().unwrap().to_vec();
+        reference.draw(text("A")).expect("to draw");
+        let frame_a = reference.backend().front_buffer().unwrap().to_vec();
+        reference.draw(text("AB")).expect("to draw");
+        let frame_ab = reference.backend().front_buffer().unwrap().to_vec();
+
+        let config = EmbeddedBackendConfig {
+            async_flush: true,
+            double_buffer: true,
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display1, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+
+        terminal.draw(text("A")).expect("to draw");
+        assert_eq!(terminal.backend().front_buffer().unwrap(), background);
+        terminal.backend_mut().swap_buffers();
+        assert_eq!(terminal.backend().front_buffer().unwrap(), frame_a);
+
+        // only "B" is drawn, "A" must have been copied forward into the back buffer
+        terminal.draw(text("AB")).expect("to draw");
+        terminal.backend_mut().swap_buffers();
+        assert_eq!(terminal.backend().front_buffer().unwrap(), frame_ab);
+
+        terminal.backend_mut().swap_buffers();
+        assert_eq!(terminal.backend().front_buffer().unwrap(), frame_ab);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn keeps_front_buffer_on_theme_change(mut display0: MockDisplay<Rgb888>) {
+        let config = EmbeddedBackendConfig {
+            async_flush: true,
+            double_buffer: true,
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display0, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal
+            .draw(|frame| frame.render_widget("A", frame.area()))
+            .expect("to draw");
+        terminal.backend_mut().swap_buffers();
+        let frame_a = terminal.backend().front_buffer().unwrap().to_vec();
+
+        let theme = ColorTheme::tokyo_night();
+        let background = theme.background;
+        terminal.backend_mut().set_color_theme(theme);
+        terminal
+            .draw(|frame| frame.render_widget("A", frame.area()))
+            .expect("to draw");
+        assert_eq!(terminal.backend().front_buffer().unwrap(), frame_a);
+
+        terminal.backend_mut().swap_buffers();
+        let front_buffer = terminal.backend().front_buffer().unwrap();
+        assert!(front_buffer.contains(&background));
+        assert!(!front_buffer.contains(&ColorTheme::ansi().background));
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn flushes_double_buffers(
+        mut display0: MockDisplay<Rgb888>,
+        mut display1: MockDisplay<Rgb888>,
+    ) {
+        let draw = |frame: &mut ratatui::Frame| frame.render_widget("Test", frame.area());
+        {
+            let backend = EmbeddedBackend::new(&mut display0, Default::default());
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal.draw(draw).expect("to draw");
+        }
+
+        let config = EmbeddedBackendConfig {
+            double_buffer: true,
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display1, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal.draw(draw).expect("to draw");
+        terminal.draw(draw).expect("to draw");
+
+        terminal.backend().display().assert_eq(&display0);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn renders_into_framebuffer_memory(
+        mut display0: MockDisplay<Rgb888>,
+        mut display1: MockDisplay<Rgb888>,
+        #[case] double_buffer: bool,
+    ) {
+        let draw = |frame: &mut ratatui::Frame| frame.render_widget("Test", frame.area());
+        {
+            let backend = EmbeddedBackend::new(&mut display0, Default::default());
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal.draw(draw).expect("to draw");
+        }
+
+        let memory = alloc::vec::Vec::leak(alloc::vec![Rgb888::RED; 2 * 64 * 64]);
+        let config = EmbeddedBackendConfig {
+            double_buffer,
+            framebuffer_memory: Some(FramebufferMemory::Pixels(memory)),
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display1, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal.draw(draw).expect("to draw");
+
+        terminal.backend().display().assert_eq(&display0);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    #[should_panic(expected = "framebuffer_memory holds 4096 elements, 8192 are needed")]
+    fn rejects_too_small_framebuffer_memory(mut display0: MockDisplay<Rgb888>) {
+        let config = EmbeddedBackendConfig {
+            double_buffer: true,
+            framebuffer_memory: Some(FramebufferMemory::Pixels(alloc::vec::Vec::leak(
+                alloc::vec![Rgb888::BLACK; 64 * 64],
+            ))),
+            ..Default::default()
+        };
+        EmbeddedBackend::new(&mut display0, config);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    #[case::heap(None, false, None)]
+    #[case::double_buffer(None, true, None)]
+    #[case::bands(Some(2), false, None)]
+    #[case::static_memory(None, true, Some(2 * 8 * 64))]
+    fn renders_packed_framebuffer(
+        #[case] band_rows: Option<u16>,
+        #[case] double_buffer: bool,
+        #[case] memory_len: Option<usize>,
+    ) {
+        let draw = |frame: &mut ratatui::Frame| frame.render_widget("Test", frame.area());
+        let mut expected = MockDisplay::<BinaryColor>::new();
+        {
+            let backend = EmbeddedBackend::new(&mut expected, Default::default());
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal.draw(draw).expect("to draw");
+        }
+
+        let mut display = MockDisplay::<BinaryColor>::new();
+        display.set_allow_overdraw(true);
+        let memory = memory_len
+            .map(|len| FramebufferMemory::Bytes(alloc::vec::Vec::leak(alloc::vec![0xAAu8; len])));
+        let config = EmbeddedBackendConfig {
+            framebuffer_layout: FramebufferLayout::packed(),
+            framebuffer_memory: memory,
+            double_buffer,
+            band_rows,
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal.draw(draw).expect("to draw");
+
+        let backend = terminal.backend();
+        backend.display().assert_eq(&expected);
+        assert_eq!(backend.front_buffer(), None);
+        if band_rows.is_none() {
+            // 8 pixels per byte, most significant bit first
+            let bytes = backend.front_buffer_bytes().unwrap();
+            assert_eq!(bytes.len(), 8 * 64);
+            for point in expected.bounding_box().points() {
+                let byte = bytes[point.y as usize * 8 + point.x as usize / 8];
+                let bit = byte >> (7 - point.x % 8) & 1;
+                assert_eq!(
+                    Some(BinaryColor::from(bit == 1)),
+                    expected.get_pixel(point),
+                    "{point:?}"
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    #[should_panic(expected = "the packed framebuffer layout needs FramebufferMemory::Bytes")]
+    fn rejects_pixel_memory_for_packed_framebuffer(mut display0: MockDisplay<Rgb888>) {
+        let config = EmbeddedBackendConfig {
+            framebuffer_layout: FramebufferLayout::packed(),
+            framebuffer_memory: Some(FramebufferMemory::Pixels(alloc::vec::Vec::leak(
+                alloc::vec![Rgb888::BLACK; 64 * 64],
+            ))),
+            ..Default::default()
+        };
+        EmbeddedBackend::new(&mut display0, config);
+    }
+
+    /// Draws every named color as the background of a cell, red text, and `rgb` as the
+    /// background of the last row.
+    #[cfg(feature = "framebuffer")]
+    fn draw_named_colors(frame: &mut ratatui::Frame, rgb: Option<style::Color>) {
+        use ratatui::style::{Color, Stylize};
+        use ratatui::text::{Line, Span, Text};
+
+        const NAMED: [Color; 16] = [
+            Color::Black,
+            Color::Red,
+            Color::Green,
+            Color::Yellow,
+            Color::Blue,
+            Color::Magenta,
+            Color::Cyan,
+            Color::Gray,
+            Color::DarkGray,
+            Color::LightRed,
+            Color::LightGreen,
+            Color::LightYellow,
+            Color::LightBlue,
+            Color::LightMagenta,
+            Color::LightCyan,
+            Color::White,
+        ];
+        let mut lines: alloc::vec::Vec<Line> = NAMED
+            .chunks(8)
+            .map(|colors| Line::from_iter(colors.iter().map(|&color| Span::from(" ").bg(color))))
+            .collect();
+        lines.push("Test".red().into());
+        lines.extend(rgb.map(|rgb| Line::from("   ".bg(rgb))));
+        frame.render_widget(Text::from(lines), frame.area());
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    #[case::four_bits(IndexBits::Four, false, None, None)]
+    #[case::eight_bits(IndexBits::Eight, false, None, None)]
+    #[case::double_buffer(IndexBits::Four, true, None, None)]
+    #[case::bands(IndexBits::Four, false, Some(2), None)]
+    #[case::static_memory(IndexBits::Eight, true, None, Some(2 * 64 * 64))]
+    fn renders_indexed_framebuffer(
+        #[case] bits: IndexBits,
+        #[case] double_buffer: bool,
+        #[case] band_rows: Option<u16>,
+        #[case] memory_len: Option<usize>,
+    ) {
+        // with 4-bit indices, the 16 theme colors fill the palette
+        let rgb = (bits == IndexBits::Eight).then_some(style::Color::Rgb(10, 200, 30));
+        let draw = |frame: &mut ratatui::Frame| draw_named_colors(frame, rgb);
+        let mut expected = display0();
+        {
+            let backend = EmbeddedBackend::new(&mut expected, Default::default());
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal.draw(draw).expect("to draw");
+        }
+
+        let mut display = display0();
+        let memory = memory_len
+            .map(|len| FramebufferMemory::Bytes(alloc::vec::Vec::leak(alloc::vec![0xAAu8; len])));
+        let config = EmbeddedBackendConfig {
+            framebuffer_layout: FramebufferLayout::indexed(bits),
+            framebuffer_memory: memory,
+            double_buffer,
+            band_rows,
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal.draw(draw).expect("to draw");
+
+        let backend = terminal.backend();
+        backend.display().assert_eq(&expected);
+        assert_eq!(backend.front_buffer(), None);
+        assert_eq!(backend.front_buffer_bytes(), None);
+        assert_eq!(backend.palette_overflows(), 0);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    #[case::four_bits(IndexBits::Four, style::Color::Red, true)]
+    #[case::eight_bits(IndexBits::Eight, style::Color::Rgb(250, 10, 10), false)]
+    fn draws_closest_color_when_palette_is_full(
+        #[case] bits: IndexBits,
+        #[case] drawn: style::Color,
+        #[case] overflows: bool,
+    ) {
+        let mut expected = display0();
+        {
+            let backend = EmbeddedBackend::new(&mut expected, Default::default());
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal
+                .draw(|frame| draw_named_colors(frame, Some(drawn)))
+                .expect("to draw");
+        }
+
+        let mut display = display0();
+        let config = EmbeddedBackendConfig {
+            framebuffer_layout: FramebufferLayout::indexed(bits),
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal
+            .draw(|frame| draw_named_colors(frame, Some(style::Color::Rgb(250, 10, 10))))
+            .expect("to draw");
+
+        // the theme colors fill the palette with 4-bit indices
+        terminal.backend().display().assert_eq(&expected);
+        assert_eq!(terminal.backend().palette_overflows() > 0, overflows);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn reserves_colors_of_new_theme() {
+        let draw = |frame: &mut ratatui::Frame| draw_named_colors(frame, None);
+        let mut expected = display0();
+        {
+            let backend = EmbeddedBackend::new(&mut expected, Default::default());
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal.draw(draw).expect("to draw");
+            terminal
+                .backend_mut()
+                .set_color_theme(ColorTheme::tokyo_night());
+            terminal.draw(draw).expect("to draw");
+        }
+
+        let mut display = display0();
+        let config = EmbeddedBackendConfig {
+            framebuffer_layout: FramebufferLayout::indexed(IndexBits::Four),
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal.draw(draw).expect("to draw");
+        terminal
+            .backend_mut()
+            .set_color_theme(ColorTheme::tokyo_night());
+        terminal.draw(draw).expect("to draw");
+
+        terminal.backend().display().assert_eq(&expected);
+        assert_eq!(terminal.backend().palette_overflows(), 0);
+    }
+
+    /// Async display which stores the transferred frames into a [`MockDisplay`].
+    #[cfg(feature = "framebuffer")]
+    struct AsyncMockDisplay {
+        display: MockDisplay<Rgb888>,
+        sync_draws: usize,
+        transfers: usize,
+    }
+
+    #[cfg(feature = "framebuffer")]
+    impl DrawTarget for AsyncMockDisplay {
+        type Color = Rgb888;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, _pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            self.sync_draws += 1;
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    impl OriginDimensions for AsyncMockDisplay {
+        fn size(&self) -> Size {
+            self.display.size()
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    impl AsyncDisplay for AsyncMockDisplay {
+        async fn write_pixels(
+            &mut self,
+            area: Rectangle,
+            pixels: &[Rgb888],
+        ) -> Result<(), Self::Error> {
+            // wait for the "transfer" once, like a DMA transfer would
+            let mut waited = false;
+            core::future::poll_fn(|context| {
+                if waited {
+                    return core::task::Poll::Ready(());
+                }
+                waited = true;
+                context.waker().wake_by_ref();
+                core::task::Poll::Pending
+            })
+            .await;
+            self.transfers += 1;
+            self.display.fill_contiguous(&area, pixels.iter().copied())
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = core::pin::pin!(future);
+        let mut context = core::task::Context::from_waker(core::task::Waker::noop());
+        loop {
+            if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    #[case::native(FramebufferLayout::native(), 1)]
+    // packed pixels are sent row by row
+    #[case::packed(FramebufferLayout::packed(), 64)]
+    fn flushes_asynchronously(
+        mut display0: MockDisplay<Rgb888>,
+        display1: MockDisplay<Rgb888>,
+        #[case] framebuffer_layout: FramebufferLayout<Rgb888>,
+        #[case] transfers: usize,
+    ) {
+        let draw = |frame: &mut ratatui::Frame| frame.render_widget("Test", frame.area());
+
+        {
+            let backend = EmbeddedBackend::new(&mut display0, Default::default());
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal.draw(draw).expect("to draw");
+        }
+
+        let mut async_display = AsyncMockDisplay {
+            display: display1,
+            sync_draws: 0,
+            transfers: 0,
+        };
+        let config = EmbeddedBackendConfig {
+            async_flush: true,
+            framebuffer_layout,
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut async_display, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal.draw(draw).expect("to draw");
+        assert_eq!(terminal.backend().display().transfers, 0);
+
+        block_on(terminal.backend_mut().flush_async()).expect("to flush");
+
+        let async_display = terminal.backend().display();
+        assert_eq!(async_display.sync_draws, 0);
+        assert_eq!(async_display.transfers, transfers);
+        async_display.display.assert_eq(&display0);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn recolors_framebuffer_background(mut display0: MockDisplay<Rgb888>) {
+        let mut backend = EmbeddedBackend::new(&mut display0, Default::default());
+        backend.set_color_theme(ColorTheme {
+            background: Rgb888::BLUE,
+            ..ColorTheme::ansi()
+        });
+        backend.flush().unwrap();
+
+        assert_eq!(
+            backend.display().get_pixel(Point::new(63, 63)),
+            Some(Rgb888::BLUE)
+        );
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn recolors_framebuffer_background_on_filter_change(mut display0: MockDisplay<Rgb888>) {
+        let mut backend = EmbeddedBackend::new(&mut display0, Default::default());
+        backend.flush().unwrap();
+        backend.set_color_filter(ColorFilter::inverted());
+        backend.flush().unwrap();
+
+        assert_eq!(
+            backend.display().get_pixel(Point::new(63, 63)),
+            Some(Rgb888::WHITE)
+        );
+    }
+
+    #[rstest]
+    fn renders_into_viewport(mut display0: MockDisplay<Rgb888>, mut display1: MockDisplay<Rgb888>) {
+        let viewport = Rectangle::new(Point::new(8, 6), Size::new(24, 12));
+        let render = |display: &mut MockDisplay<Rgb888>, config: EmbeddedBackendConfig<_, _>| {
+            let backend = EmbeddedBackend::new(display, config);
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal
+                .draw(|frame| {
+                    use ratatui::text::Line;
+                    let content = Line::from("Test").left_aligned();
+                    frame.render_widget(content, frame.area());
+                })
+                .expect("to draw");
+            assert_eq!(terminal.size().unwrap(), layout::Size::new(6, 2));
+        };
+
+        render(
+            &mut display0,
+            EmbeddedBackendConfig {
+                font_regular: FONT_4X6,
+                viewport: Some(Rectangle::new(Point::zero(), viewport.size)),
+                ..Default::default()
+            },
+        );
+        render(
+            &mut display1,
+            EmbeddedBackendConfig {
+                font_regular: FONT_4X6,
+                viewport: Some(viewport),
+                ..Default::default()
+            },
+        );
+
+        for point in display1.bounding_box().points() {
+            if viewport.contains(point) {
+                assert_eq!(
+                    display1.get_pixel(point),
+                    display0.get_pixel(point - viewport.top_left)
+                );
+            } else {
+                assert_eq!(display1.get_pixel(point), None);
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(Rotation::Rotate90, false, false)]
+    #[case(Rotation::Rotate180, false, false)]
+    #[case(Rotation::Rotate270, false, false)]
+    #[case(Rotation::Rotate0, true, false)]
+    #[case(Rotation::Rotate0, false, true)]
+    #[case(Rotation::Rotate90, true, true)]
+    fn renders_transformed(
+        mut display0: MockDisplay<Rgb888>,
+        mut display1: MockDisplay<Rgb888>,
+        #[case] rotation: Rotation,
+        #[case] mirror_horizontal: bool,
+        #[case] mirror_vertical: bool,
+    ) {
+        let render = |display: &mut MockDisplay<Rgb888>, config: EmbeddedBackendConfig<_, _>| {
+            let backend = EmbeddedBackend::new(display, config);
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal
+                .draw(|frame| {
+                    use ratatui::text::Line;
+                    let content = Line::from("Test").left_aligned();
+                    frame.render_widget(content, frame.area());
+                })
+                .expect("to draw");
+        };
+
+        render(
+            &mut display0,
+            EmbeddedBackendConfig {
+                font_regular: FONT_4X6,
+                ..Default::default()
+            },
+        );
+        render(
+            &mut display1,
+            EmbeddedBackendConfig {
+                font_regular: FONT_4X6,
+                rotation,
+                mirror_horizontal,
+                mirror_vertical,
+                ..Default::default()
+            },
+        );
+
+        let transform = crate::transform::Transform::new(
+            display1.bounding_box(),
+            rotation,
+            mirror_horizontal,
+            mirror_vertical,
+        );
+        for point in display0.bounding_box().points() {
+            assert_eq!(
+                display0.get_pixel(point),
+                display1.get_pixel(transform.map_point(point))
+            );
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    fn draw_lines(frame: &mut ratatui::Frame, first: &'static str) {
+        use ratatui::style::Stylize;
+        use ratatui::text::{Line, Text};
+        let text = Text::from(alloc::vec![
+            Line::from(first),
+            Line::from("rendering").red().on_blue(),
+            Line::from("of"),
+            Line::from("cells").on_green(),
+            Line::from("xyz"),
+            Line::from("end").yellow(),
+        ]);
+        frame.render_widget(text, frame.area());
+    }
+
+    #[cfg(feature = "framebuffer")]
+    fn band_config(rotation: Rotation) -> EmbeddedBackendConfig<MockDisplay<Rgb888>, Rgb888> {
+        EmbeddedBackendConfig {
+            font_regular: FONT_6X10,
+            font_bold: None,
+            vertical_alignment: TerminalAlignment::Center,
+            rotation,
+            ..Default::default()
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn renders_bands_as_framebuffer(
+        mut display0: MockDisplay<Rgb888>,
+        mut display1: MockDisplay<Rgb888>,
+        #[values(1, 2, 4, 100)] band_rows: u16,
+        #[values(Rotation::Rotate0, Rotation::Rotate90)] rotation: Rotation,
+    ) {
+        {
+            let backend = EmbeddedBackend::new(&mut display0, band_config(rotation));
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal
+                .draw(|frame| draw_lines(frame, "Bands"))
+                .expect("to draw");
+        }
+
+        let config = EmbeddedBackendConfig {
+            band_rows: Some(band_rows),
+            ..band_config(rotation)
+        };
+        let backend = EmbeddedBackend::new(&mut display1, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal
+            .draw(|frame| draw_lines(frame, "Band"))
+            .expect("to draw");
+        // Ratatui only draws the changed cells, the others are kept by the backend
+        terminal
+            .draw(|frame| draw_lines(frame, "Bands"))
+            .expect("to draw");
+
+        terminal.backend().display().assert_eq(&display0);
+    }
+
+    /// Display counting the calls of `fill_contiguous`.
+    #[cfg(feature = "framebuffer")]
+    struct CountingDisplay {
+        display: MockDisplay<Rgb888>,
+        fills: alloc::vec::Vec<Rectangle>,
+    }
+
+    #[cfg(feature = "framebuffer")]
+    impl DrawTarget for CountingDisplay {
+        type Color = Rgb888;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            self.display.draw_iter(pixels)
+        }
+
+        fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Self::Color>,
+        {
+            self.fills.push(*area);
+            self.display.fill_contiguous(area, colors)
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    impl OriginDimensions for CountingDisplay {
+        fn size(&self) -> Size {
+            self.display.size()
+        }
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn sends_one_fill_per_band(display0: MockDisplay<Rgb888>) {
+        let mut display = CountingDisplay {
+            display: display0,
+            fills: alloc::vec::Vec::new(),
+        };
+        let config = EmbeddedBackendConfig {
+            font_regular: FONT_6X10,
+            band_rows: Some(2),
+            // a band of 64 × 20 pixels
+            framebuffer_memory: Some(FramebufferMemory::Pixels(alloc::vec::Vec::leak(
+                alloc::vec![Rgb888::RED; 64 * 20],
+            ))),
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal
+            .draw(|frame| draw_lines(frame, "Band"))
+            .expect("to draw");
+
+        assert_eq!(
+            terminal.backend().display().fills,
+            [0, 20, 40, 60].map(|y| Rectangle::new(
+                Point::new(0, y),
+                Size::new(64, if y == 60 { 4 } else { 20 })
+            ))
+        );
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn resizes_bands_with_fonts(
+        mut display0: MockDisplay<Rgb888>,
+        mut display1: MockDisplay<Rgb888>,
+    ) {
+        {
+            let backend = EmbeddedBackend::new(&mut display0, band_config(Rotation::Rotate0));
+            let mut terminal = Terminal::new(backend).expect("to create terminal");
+            terminal
+                .draw(|frame| draw_lines(frame, "Bands"))
+                .expect("to draw");
+        }
+
+        display1.set_allow_overdraw(true);
+        let mut display = CountingDisplay {
+            display: display1,
+            fills: alloc::vec::Vec::new(),
+        };
+        let config = EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            font_bold: None,
+            vertical_alignment: TerminalAlignment::Center,
+            band_rows: Some(2),
+            ..Default::default()
+        };
+        let backend = EmbeddedBackend::new(&mut display, config);
+        let mut terminal = Terminal::new(backend).expect("to create terminal");
+        terminal
+            .draw(|frame| draw_lines(frame, "Band"))
+            .expect("to draw");
+        terminal
+            .backend_mut()
+            .set_fonts(FONT_6X10, None, None)
+            .expect("to resize the bands");
+        terminal.backend_mut().display_mut().fills.clear();
+        terminal
+            .draw(|frame| draw_lines(frame, "Bands"))
+            .expect("to draw");
+
+        let display = terminal.backend().display();
+        // bands of two rows of the new font
+        assert_eq!(
+            display.fills,
+            [0, 20, 40, 60].map(|y| Rectangle::new(
+                Point::new(0, y),
+                Size::new(64, if y == 60 { 4 } else { 20 })
+            ))
+        );
+        display.display.assert_eq(&display0);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn rejects_fonts_too_large_for_framebuffer_memory(mut display0: MockDisplay<Rgb888>) {
+        let config = EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            band_rows: Some(2),
+            // a band of 64 × 12 pixels
+            framebuffer_memory: Some(FramebufferMemory::Pixels(alloc::vec::Vec::leak(
+                alloc::vec![Rgb888::RED; 64 * 12],
+            ))),
+            ..Default::default()
+        };
+        let mut backend = EmbeddedBackend::new(&mut display0, config);
+
+        let error = backend.set_fonts(FONT_6X10, None, None).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::FramebufferMemoryTooSmall {
+                needed: 1280,
+                available: 768
+            }
+        ));
+        assert_eq!(backend.size().unwrap(), layout::Size::new(16, 10));
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    #[should_panic(expected = "band_rows can't be combined with async_flush or double_buffer")]
+    fn rejects_double_buffered_bands(mut display0: MockDisplay<Rgb888>) {
+        let config = EmbeddedBackendConfig {
+            band_rows: Some(2),
+            double_buffer: true,
+            ..Default::default()
+        };
+        EmbeddedBackend::new(&mut display0, config);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    #[should_panic(expected = "band_rows must not be 0")]
+    fn rejects_empty_bands(mut display0: MockDisplay<Rgb888>) {
+        let config = EmbeddedBackendConfig {
+            band_rows: Some(0),
+            ..Default::default()
+        };
+        EmbeddedBackend::new(&mut display0, config);
+    }
 }
@@ -0,0 +1,346 @@
+//! Drawing of terminal cells.
+//!
+//! Glyphs are drawn with [`PixelRole`]s instead of display colors. [`CellTarget`] resolves the
+//! role of every pixel to a display color, which allows the color to depend on the pixel position
+//! (e.g. for [dithering](crate::Dithering)).
+
+use embedded_graphics::Pixel;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Dimensions, Point};
+use embedded_graphics::pixelcolor::PixelColor;
+use embedded_graphics::primitives::{PointsIter, Rectangle};
+
+use ratatui_core::style::Color;
+
+use crate::color_mapper::ColorMapper;
+use crate::colors::{ColorTheme, TermColorType};
+use crate::dither::Dithering;
+
+/// Role of a pixel within a cell.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum PixelRole {
+    Background,
+    Foreground,
+    #[cfg_attr(not(feature = "underline-color"), allow(dead_code))]
+    Underline,
+}
+
+impl PixelColor for PixelRole {
+    type Raw = ();
+}
+
+/// All roles, in the order of their discriminants.
+const ROLES: [PixelRole; 3] = [
+    PixelRole::Background,
+    PixelRole::Foreground,
+    PixelRole::Underline,
+];
+
+/// Colors of a cell, indexed by [`PixelRole`].
+pub(crate) struct CellColors {
+    pub(crate) background: Color,
+    pub(crate) foreground: Color,
+    pub(crate) underline: Color,
+}
+
+impl CellColors {
+    fn get(&self, role: PixelRole) -> (Color, TermColorType) {
+        match role {
+            PixelRole::Background => (self.background, TermColorType::Background),
+            PixelRole::Foreground => (self.foreground, TermColorType::Foreground),
+            PixelRole::Underline => (self.underline, TermColorType::Foreground),
+        }
+    }
+
+    /// Returns the display colors of the roles, indexed by [`PixelRole`].
+    pub(crate) fn resolve<C>(&self, mut map: impl FnMut(Color, TermColorType) -> C) -> [C; 3] {
+        ROLES.map(|role| {
+            let (color, color_type) = self.get(role);
+            map(color, color_type)
+        })
+    }
+
+    /// Replaces every color with `map(color, color_type)`.
+    pub(crate) fn map(self, map: impl Fn(Color, TermColorType) -> Color) -> Self {
+        Self {
+            background: map(self.background, TermColorType::Background),
+            foreground: map(self.foreground, TermColorType::Foreground),
+            underline: map(self.underline, TermColorType::Foreground),
+        }
+    }
+
+    /// Replaces the foreground and underline colors with black or white if their display colors
+    /// are the same as the one of the background.
+    fn ensure_contrast<C: PixelColor>(
+        &mut self,
+        resolved: &mut [C; 3],
+        color_mapper: &dyn ColorMapper<C>,
+        theme: &ColorTheme,
+    ) {
+        const CONTRASTING: [Color; 2] = [Color::Rgb(0, 0, 0), Color::Rgb(255, 255, 255)];
+
+        let background = resolved[PixelRole::Background as usize];
+        for (role, color) in [
+            (PixelRole::Foreground, &mut self.foreground),
+            (PixelRole::Underline, &mut self.underline),
+        ] {
+            if resolved[role as usize] != background {
+                continue;
+            }
+            let contrasting = CONTRASTING
+                .into_iter()
+                .map(|c| (c, color_mapper.map(c, TermColorType::Foreground, theme)))
+                .find(|&(_, native)| native != background);
+            if let Some((contrasting, native)) = contrasting {
+                *color = contrasting;
+                resolved[role as usize] = native;
+            }
+        }
+    }
+}
+
+/// Resolves [`PixelRole`]s to the display colors of a cell.
+struct CellPalette<'a, C> {
+    colors: CellColors,
+    theme: &'a ColorTheme,
+    color_mapper: &'a dyn ColorMapper<C>,
+    dithering: Option<Dithering>,
+    /// Display colors of the roles, used when not dithering.
+    resolved: [C; 3],
+}
+
+impl<C: PixelColor> CellPalette<'_, C> {
+    fn color(&self, point: Point, role: PixelRole) -> C {
+        match self.dithering {
+            None => self.resolved[role as usize],
+            Some(dithering) => {
+                let (color, color_type) = self.colors.get(role);
+                self.color_mapper.map_dithered(
+                    color,
+                    color_type,
+                    self.theme,
+                    dithering.threshold(point),
+                )
+            }
+        }
+    }
+}
+
+/// A [`DrawTarget`] adapter that resolves [`PixelRole`]s to the colors of a cell.
+pub(crate) struct CellTarget<'a, T: DrawTarget> {
+    target: &'a mut T,
+    palette: CellPalette<'a, T::Color>,
+}
+
+impl<'a, T: DrawTarget> CellTarget<'a, T> {
+    pub(crate) fn new(
+        target: &'a mut T,
+        mut colors: CellColors,
+        mut resolved: [T::Color; 3],
+        theme: &'a ColorTheme,
+        color_mapper: &'a dyn ColorMapper<T::Color>,
+        dithering: Option<Dithering>,
+        contrast_guard: bool,
+    ) -> Self {
+        if contrast_guard {
+            colors.ensure_contrast(&mut resolved, color_mapper, theme);
+        }
+        Self {
+            target,
+            palette: CellPalette {
+                colors,
+                theme,
+                color_mapper,
+                dithering,
+                resolved,
+            },
+        }
+    }
+}
+
+impl<T: DrawTarget> Dimensions for CellTarget<'_, T> {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<T: DrawTarget> DrawTarget for CellTarget<'_, T> {
+    type Color = PixelRole;
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let palette = &self.palette;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, role)| Pixel(point, palette.color(point, role))),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let palette = &self.palette;
+        match palette.dithering {
+            None => self.target.fill_contiguous(
+                area,
+                colors
+                    .into_iter()
+                    .map(|role| palette.resolved[role as usize]),
+            ),
+            Some(_) => self.target.fill_contiguous(
+                area,
+                area.points()
+                    .zip(colors)
+                    .map(|(point, role)| palette.color(point, role)),
+            ),
+        }
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, role: Self::Color) -> Result<(), Self::Error> {
+        let palette = &self.palette;
+        match palette.dithering {
+            None => self
+                .target
+                .fill_solid(area, palette.resolved[role as usize]),
+            Some(_) => self
+                .target
+                .fill_contiguous(area, area.points().map(|point| palette.color(point, role))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::pixelcolor::BinaryColor;
+
+    use crate::color_mapper::DefaultColorMapper;
+
+    /// Resolves the colors of a cell to the display colors of the roles.
+    fn resolve<C: PixelColor>(
+        (background, foreground): (Color, Color),
+        contrast_guard: bool,
+    ) -> [C; 3]
+    where
+        DefaultColorMapper: ColorMapper<C>,
+    {
+        let mut display = MockDisplay::<C>::new();
+        let colors = CellColors {
+            background,
+            foreground,
+            underline: foreground,
+        };
+        let theme = ColorTheme::default();
+        let resolved =
+            colors.resolve(|color, color_type| DefaultColorMapper.map(color, color_type, &theme));
+        let target = CellTarget::new(
+            &mut display,
+            colors,
+            resolved,
+            &theme,
+            &DefaultColorMapper,
+            None,
+            contrast_guard,
+        );
+        target.palette.resolved
+    }
+
+    #[rstest]
+    #[case((Color::White, Color::Gray), BinaryColor::On, BinaryColor::Off)]
+    #[case((Color::Black, Color::Black), BinaryColor::Off, BinaryColor::On)]
+    #[case((Color::Reset, Color::Rgb(0, 0, 0)), BinaryColor::Off, BinaryColor::On)]
+    fn guards_binary_contrast(
+        #[case] colors: (Color, Color),
+        #[case] background: BinaryColor,
+        #[case] foreground: BinaryColor,
+    ) {
+        assert_eq!(
+            resolve::<BinaryColor>(colors, false),
+            [background, background, background]
+        );
+        assert_eq!(
+            resolve::<BinaryColor>(colors, true),
+            [background, foreground, foreground]
+        );
+    }
+
+    #[rstest]
+    #[case((Color::White, Color::Blue), BinaryColor::On, BinaryColor::Off)]
+    #[case((Color::Red, Color::Green), BinaryColor::Off, BinaryColor::On)]
+    fn keeps_distinct_colors(
+        #[case] colors: (Color, Color),
+        #[case] background: BinaryColor,
+        #[case] foreground: BinaryColor,
+    ) {
+        assert_eq!(
+            resolve::<BinaryColor>(colors, true),
+            [background, foreground, foreground]
+        );
+    }
+
+    #[cfg(feature = "epd-weact")]
+    #[rstest]
+    #[case((Color::White, Color::LightCyan), weact_studio_epd::TriColor::White, weact_studio_epd::TriColor::Black)]
+    #[case((Color::Red, Color::LightRed), weact_studio_epd::TriColor::Red, weact_studio_epd::TriColor::Black)]
+    #[case((Color::Black, Color::Blue), weact_studio_epd::TriColor::Black, weact_studio_epd::TriColor::White)]
+    fn guards_weact_tricolor_contrast(
+        #[case] colors: (Color, Color),
+        #[case] background: weact_studio_epd::TriColor,
+        #[case] foreground: weact_studio_epd::TriColor,
+    ) {
+        assert_eq!(
+            resolve::<weact_studio_epd::TriColor>(colors, false),
+            [background, background, background]
+        );
+        assert_eq!(
+            resolve::<weact_studio_epd::TriColor>(colors, true),
+            [background, foreground, foreground]
+        );
+    }
+
+    #[cfg(feature = "epd-waveshare")]
+    #[rstest]
+    #[case((Color::White, Color::Gray), epd_waveshare::color::Color::White, epd_waveshare::color::Color::Black)]
+    #[case((Color::Black, Color::Black), epd_waveshare::color::Color::Black, epd_waveshare::color::Color::White)]
+    fn guards_waveshare_contrast(
+        #[case] colors: (Color, Color),
+        #[case] background: epd_waveshare::color::Color,
+        #[case] foreground: epd_waveshare::color::Color,
+    ) {
+        assert_eq!(
+            resolve::<epd_waveshare::color::Color>(colors, false),
+            [background, background, background]
+        );
+        assert_eq!(
+            resolve::<epd_waveshare::color::Color>(colors, true),
+            [background, foreground, foreground]
+        );
+    }
+
+    #[cfg(feature = "epd-waveshare")]
+    #[rstest]
+    #[case((Color::White, Color::LightCyan), epd_waveshare::color::TriColor::White, epd_waveshare::color::TriColor::Black)]
+    #[case((Color::Red, Color::LightRed), epd_waveshare::color::TriColor::Chromatic, epd_waveshare::color::TriColor::Black)]
+    fn guards_waveshare_tricolor_contrast(
+        #[case] colors: (Color, Color),
+        #[case] background: epd_waveshare::color::TriColor,
+        #[case] foreground: epd_waveshare::color::TriColor,
+    ) {
+        assert_eq!(
+            resolve::<epd_waveshare::color::TriColor>(colors, false),
+            [background, background, background]
+        );
+        assert_eq!(
+            resolve::<epd_waveshare::color::TriColor>(colors, true),
+            [background, foreground, foreground]
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! # Color post-processing
+//!
+//! A [`ColorFilter`] adjusts colors after the [`ColorTheme`](crate::ColorTheme) resolved them,
+//! e.g. for a night mode, a high contrast mode or an inverted mode.
+
+use alloc::vec::Vec;
+
+use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+use ratatui_core::style::Color;
+
+use crate::colors::{ColorTheme, TermColorType};
+use crate::dither::luma;
+
+/// Order of the color channels, see [`ColorAdjustment::SwapChannels`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelOrder {
+    /// Red, blue, green.
+    Rbg,
+    /// Green, red, blue.
+    Grb,
+    /// Green, blue, red.
+    Gbr,
+    /// Blue, red, green.
+    Brg,
+    /// Blue, green, red.
+    Bgr,
+}
+
+/// A single step of a [`ColorFilter`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorAdjustment {
+    /// Adds the value (`-255..=255`) to every channel.
+    Brightness(i16),
+    /// Applies gamma correction: `channel = (channel / 255) ^ gamma * 255`.
+    ///
+    /// Values above `1.0` darken and values below `1.0` brighten the midtones.
+    /// Negative values are treated as `0.0`.
+    Gamma(f32),
+    /// Inverts the color.
+    Invert,
+    /// Reorders the channels, e.g. for displays with swapped red and blue.
+    SwapChannels(ChannelOrder),
+    /// Shifts the white point (`-255..=255`).
+    ///
+    /// Negative values give warmer (red-shifted) colors, positive values cooler (blue) ones.
+    Temperature(i16),
+    /// Scales the distance of every channel from the middle gray.
+    ///
+    /// Values above `1.0` increase and values below `1.0` decrease the contrast.
+    Contrast(f32),
+    /// Replaces the color with a gray of the same luma.
+    Grayscale,
+}
+
+impl ColorAdjustment {
+    /// Applies the adjustment to a color.
+    pub fn apply(self, color: Rgb888) -> Rgb888 {
+        let (r, g, b) = (color.r(), color.g(), color.b());
+        match self {
+            ColorAdjustment::Brightness(value) => {
+                map_channels(color, |c| clamp(c as i32 + value as i32))
+            }
+            ColorAdjustment::Gamma(gamma) => map_channels(color, |c| {
+                clamp((powf(c as f32 / 255.0, gamma.max(0.0)) * 255.0 + 0.5) as i32)
+            }),
+            ColorAdjustment::Invert => map_channels(color, |c| 255 - c),
+            ColorAdjustment::SwapChannels(order) => match order {
+                ChannelOrder::Rbg => Rgb888::new(r, b, g),
+                ChannelOrder::Grb => Rgb888::new(g, r, b),
+                ChannelOrder::Gbr => Rgb888::new(g, b, r),
+                ChannelOrder::Brg => Rgb888::new(b, r, g),
+                ChannelOrder::Bgr => Rgb888::new(b, g, r),
+            },
+            ColorAdjustment::Temperature(value) => {
+                let scale = |c: u8, amount: i32| clamp(c as i32 * (255 - amount) / 255);
+                let value = value.clamp(-255, 255) as i32;
+                if value < 0 {
+                    Rgb888::new(r, scale(g, -value / 2), scale(b, -value))
+                } else {
+                    Rgb888::new(scale(r, value), scale(g, value / 2), b)
+                }
+            }
+            ColorAdjustment::Contrast(contrast) => map_channels(color, |c| {
+                let value = (c as f32 - 127.5) * contrast + 127.5;
+                clamp((value + 0.5) as i32)
+            }),
+            ColorAdjustment::Grayscale => {
+                let luma = luma(color);
+                Rgb888::new(luma, luma, luma)
+            }
+        }
+    }
+}
+
+fn map_channels(color: Rgb888, f: impl Fn(u8) -> u8) -> Rgb888 {
+    Rgb888::new(f(color.r()), f(color.g()), f(color.b()))
+}
+
+fn clamp(value: i32) -> u8 {
+    value.clamp(0, 255) as u8
+}
+
+/// `x ^ y` for `x` in `0.0..=1.0` and non-negative `y`, precise enough for 8-bit channels.
+///
+/// `f32::powf` is not available in `core`.
+fn powf(x: f32, y: f32) -> f32 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    // log2(x) = exponent + log2(mantissa), with the mantissa in 1.0..2.0
+    let bits = x.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127;
+    let mantissa = f32::from_bits((bits & 0x007f_ffff) | 0x3f80_0000);
+    let t = (mantissa - 1.0) / (mantissa + 1.0);
+    let t2 = t * t;
+    let ln_mantissa = 2.0 * t * (1.0 + t2 * (1.0 / 3.0 + t2 * (1.0 / 5.0 + t2 / 7.0)));
+    let log2 = exponent as f32 + ln_mantissa * core::f32::consts::LOG2_E;
+
+    // 2 ^ (y * log2(x)) = 2 ^ integer * e ^ (fraction * ln(2))
+    let power = y * log2;
+    let mut integer = power as i32;
+    if (integer as f32) > power {
+        integer -= 1;
+    }
+    if integer < -126 {
+        return 0.0;
+    }
+    let z = (power - integer as f32) * core::f32::consts::LN_2;
+    let exp = 1.0
+        + z * (1.0
+            + z / 2.0 * (1.0 + z / 3.0 * (1.0 + z / 4.0 * (1.0 + z / 5.0 * (1.0 + z / 6.0)))));
+    exp * f32::from_bits(((integer + 127) as u32) << 23)
+}
+
+/// Adjustments applied to colors after the theme resolved them.
+///
+/// Adjustments are applied in the order they were added.
+/// The display colors are then mapped from the adjusted colors by the
+/// [`ColorMapper`](crate::ColorMapper).
+///
+/// # Examples
+///
+/// ```rust
+/// use mousefood::{ColorAdjustment, ColorFilter};
+///
+/// let dimmed_night_mode = ColorFilter::night_mode().then(ColorAdjustment::Brightness(-64));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColorFilter {
+    adjustments: Vec<ColorAdjustment>,
+}
+
+impl ColorFilter {
+    /// Creates a filter which leaves colors unchanged.
+    pub const fn new() -> Self {
+        Self {
+            adjustments: Vec::new(),
+        }
+    }
+
+    /// Red-shifted colors for use in the dark.
+    pub fn night_mode() -> Self {
+        Self::new().then(ColorAdjustment::Temperature(-255))
+    }
+
+    /// Increased contrast for better readability.
+    pub fn high_contrast() -> Self {
+        Self::new().then(ColorAdjustment::Contrast(2.0))
+    }
+
+    /// Inverted colors.
+    pub fn inverted() -> Self {
+        Self::new().then(ColorAdjustment::Invert)
+    }
+
+    /// Shades of gray.
+    pub fn grayscale() -> Self {
+        Self::new().then(ColorAdjustment::Grayscale)
+    }
+
+    /// Adds an adjustment after the existing ones.
+    #[must_use]
+    pub fn then(mut self, adjustment: ColorAdjustment) -> Self {
+        self.adjustments.push(adjustment);
+        self
+    }
+
+    /// Returns the adjustments of the filter.
+    pub fn adjustments(&self) -> &[ColorAdjustment] {
+        &self.adjustments
+    }
+
+    /// Applies all adjustments to a color.
+    pub fn apply(&self, color: Rgb888) -> Rgb888 {
+        self.adjustments
+            .iter()
+            .fold(color, |color, adjustment| adjustment.apply(color))
+    }
+
+    /// Resolves and adjusts a Ratatui color.
+    ///
+    /// Returns the color unchanged if the filter has no adjustments, so that color mappers still
+    /// see named colors.
+    pub(crate) fn filter(
+        &self,
+        color: Color,
+        color_type: TermColorType,
+        theme: &ColorTheme,
+    ) -> Color {
+        if self.adjustments.is_empty() {
+            return color;
+        }
+        let rgb = self.apply(theme.resolve(color, color_type));
+        Color::Rgb(rgb.r(), rgb.g(), rgb.b())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const COLOR: Rgb888 = Rgb888::new(200, 100, 50);
+
+    #[rstest]
+    #[case(ColorAdjustment::Brightness(60), Rgb888::new(255, 160, 110))]
+    #[case(ColorAdjustment::Brightness(-60), Rgb888::new(140, 40, 0))]
+    #[case(ColorAdjustment::Gamma(1.0), COLOR)]
+    #[case(ColorAdjustment::Gamma(2.0), Rgb888::new(157, 39, 10))]
+    #[case(ColorAdjustment::Gamma(0.5), Rgb888::new(226, 160, 113))]
+    #[case(ColorAdjustment::Invert, Rgb888::new(55, 155, 205))]
+    #[case(
+        ColorAdjustment::SwapChannels(ChannelOrder::Bgr),
+        Rgb888::new(50, 100, 200)
+    )]
+    #[case(
+        ColorAdjustment::SwapChannels(ChannelOrder::Gbr),
+        Rgb888::new(100, 50, 200)
+    )]
+    #[case(ColorAdjustment::Temperature(0), COLOR)]
+    #[case(ColorAdjustment::Temperature(-255), Rgb888::new(200, 50, 0))]
+    #[case(ColorAdjustment::Temperature(255), Rgb888::new(0, 50, 50))]
+    #[case(ColorAdjustment::Contrast(1.0), COLOR)]
+    #[case(ColorAdjustment::Contrast(2.0), Rgb888::new(255, 73, 0))]
+    #[case(ColorAdjustment::Contrast(0.0), Rgb888::new(128, 128, 128))]
+    #[case(ColorAdjustment::Grayscale, Rgb888::new(124, 124, 124))]
+    fn applies_adjustment(#[case] adjustment: ColorAdjustment, #[case] expected: Rgb888) {
+        assert_eq!(adjustment.apply(COLOR), expected);
+    }
+
+    #[rstest]
+    #[case(0.5, 2.0, 0.25)]
+    #[case(0.25, 0.5, 0.5)]
+    #[case(0.8, 2.2, 0.612_066)]
+    #[case(0.01, 0.45, 0.125_893)]
+    #[case(1.0, 3.0, 1.0)]
+    fn computes_power(#[case] x: f32, #[case] y: f32, #[case] expected: f32) {
+        assert!(
+            (powf(x, y) - expected).abs() < 1e-4,
+            "{x} ^ {y} = {}",
+            powf(x, y)
+        );
+    }
+
+    #[rstest]
+    fn applies_adjustments_in_order() {
+        let invert_then_darken = ColorFilter::inverted().then(ColorAdjustment::Brightness(-100));
+        let darken_then_invert = ColorFilter::new()
+            .then(ColorAdjustment::Brightness(-100))
+            .then(ColorAdjustment::Invert);
+
+        assert_eq!(invert_then_darken.apply(Rgb888::WHITE), Rgb888::BLACK);
+        assert_eq!(
+            darken_then_invert.apply(Rgb888::WHITE),
+            Rgb888::new(100, 100, 100)
+        );
+    }
+
+    #[rstest]
+    fn filters_resolved_colors() {
+        let theme = ColorTheme::ansi();
+
+        assert_eq!(
+            ColorFilter::new().filter(Color::Reset, TermColorType::Foreground, &theme),
+            Color::Reset
+        );
+        assert_eq!(
+            ColorFilter::inverted().filter(Color::Reset, TermColorType::Foreground, &theme),
+            Color::Rgb(0, 0, 0)
+        );
+        assert_eq!(
+            ColorFilter::night_mode().filter(Color::White, TermColorType::Background, &theme),
+            Color::Rgb(255, 128, 0)
+        );
+    }
+}
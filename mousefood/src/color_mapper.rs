@@ -0,0 +1,134 @@
+//! # Mapping of terminal colors to display colors
+//!
+//! [`EmbeddedBackend`](crate::EmbeddedBackend) draws every cell with colors returned by a
+//! [`ColorMapper`]. [`DefaultColorMapper`] supports the pixel color types of embedded-graphics
+//! and the EPD drivers; custom mappers allow other pixel color types or a different mapping.
+
+use embedded_graphics::pixelcolor::{
+    Bgr555, Bgr565, Bgr666, Bgr888, BinaryColor, Gray2, Gray4, Gray8, PixelColor, Rgb555, Rgb565,
+    Rgb666, Rgb888,
+};
+use ratatui_core::style::Color;
+
+use crate::colors::{ColorTheme, TermColor, TermColorType};
+use crate::ink_palette::InkPalette;
+use crate::macros::{for_all_gray_colors, for_all_rgb_colors};
+
+/// Maps Ratatui colors to display colors.
+///
+/// # Examples
+///
+/// A mapper for an LED matrix that can only switch LEDs on or off:
+///
+/// ```rust
+/// use mousefood::embedded_graphics::pixelcolor::PixelColor;
+/// use mousefood::{ColorMapper, ColorTheme, TermColorType};
+/// use ratatui::style::Color;
+///
+/// #[derive(Clone, Copy, PartialEq)]
+/// struct Led(bool);
+///
+/// impl PixelColor for Led {
+///     type Raw = ();
+/// }
+///
+/// struct LedMapper;
+///
+/// impl ColorMapper<Led> for LedMapper {
+///     fn map(&self, color: Color, color_type: TermColorType, theme: &ColorTheme) -> Led {
+///         let rgb = theme.resolve(color, color_type);
+///         Led(rgb != theme.background)
+///     }
+/// }
+/// ```
+pub trait ColorMapper<C: PixelColor> {
+    /// Maps a Ratatui color in the given role to a display color.
+    fn map(&self, color: Color, color_type: TermColorType, theme: &ColorTheme) -> C;
+
+    /// Maps a Ratatui color of a pixel with the given [dithering](crate::Dithering) threshold.
+    ///
+    /// Called instead of [`map`](Self::map) when dithering is enabled.
+    /// Pixels of a color should be lit with a probability proportional to how far the color is
+    /// above the `threshold` (`1..=255`).
+    /// The default implementation ignores the threshold.
+    fn map_dithered(
+        &self,
+        color: Color,
+        color_type: TermColorType,
+        theme: &ColorTheme,
+        threshold: u8,
+    ) -> C {
+        let _ = threshold;
+        self.map(color, color_type, theme)
+    }
+}
+
+/// Default color mapping of the supported pixel color types.
+///
+/// - RGB colors use the color of the theme.
+/// - Grayscale colors use the luma of the theme color.
+/// - `BinaryColor` keeps black and white, and maps other foreground colors to `On`
+///   and background colors to `Off`.
+/// - Three- and seven-color EPD colors use the nearest ink.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultColorMapper;
+
+macro_rules! impl_default_color_mapper {
+    (
+        $color_type:ty
+    ) => {
+        impl ColorMapper<$color_type> for DefaultColorMapper {
+            fn map(
+                &self,
+                color: Color,
+                color_type: TermColorType,
+                theme: &ColorTheme,
+            ) -> $color_type {
+                TermColor::new(color, color_type, theme).into()
+            }
+
+            fn map_dithered(
+                &self,
+                color: Color,
+                color_type: TermColorType,
+                theme: &ColorTheme,
+                threshold: u8,
+            ) -> $color_type {
+                TermColor::new(color, color_type, theme)
+                    .dithered(threshold)
+                    .into()
+            }
+        }
+    };
+}
+
+for_all_rgb_colors!(impl_default_color_mapper);
+for_all_gray_colors!(impl_default_color_mapper);
+impl_default_color_mapper!(BinaryColor);
+#[cfg(feature = "epd-weact")]
+impl_default_color_mapper!(weact_studio_epd::Color);
+#[cfg(feature = "epd-weact")]
+impl_default_color_mapper!(weact_studio_epd::TriColor);
+#[cfg(feature = "epd-waveshare")]
+impl_default_color_mapper!(epd_waveshare::color::Color);
+#[cfg(feature = "epd-waveshare")]
+impl_default_color_mapper!(epd_waveshare::color::TriColor);
+#[cfg(feature = "epd-waveshare")]
+impl_default_color_mapper!(epd_waveshare::color::OctColor);
+
+/// Maps colors to the perceptually nearest ink of the palette.
+impl<C: PixelColor> ColorMapper<C> for InkPalette<C> {
+    fn map(&self, color: Color, color_type: TermColorType, theme: &ColorTheme) -> C {
+        self.nearest(theme.resolve(color, color_type))
+    }
+
+    fn map_dithered(
+        &self,
+        color: Color,
+        color_type: TermColorType,
+        theme: &ColorTheme,
+        threshold: u8,
+    ) -> C {
+        self.dithered(theme.resolve(color, color_type), threshold)
+    }
+}
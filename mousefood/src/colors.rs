@@ -1,6 +1,10 @@
-use crate::macros::for_all_rgb_colors;
+use crate::dither::{luma, quantize};
+#[cfg(any(feature = "epd-weact", feature = "epd-waveshare"))]
+use crate::ink_palette::InkPalette;
+use crate::macros::{for_all_gray_colors, for_all_rgb_colors};
 use embedded_graphics::pixelcolor::{
-    Bgr555, Bgr565, Bgr666, Bgr888, BinaryColor, Rgb555, Rgb565, Rgb666, Rgb888, RgbColor,
+    Bgr555, Bgr565, Bgr666, Bgr888, BinaryColor, Gray2, Gray4, Gray8, GrayColor, Rgb555, Rgb565,
+    Rgb666, Rgb888, RgbColor,
 };
 use ratatui_core::style::Color;
 
@@ -43,6 +47,12 @@ pub struct ColorTheme {
     pub gray: Rgb888,
     /// ANSI dark gray mapping.
     pub dark_gray: Rgb888,
+    /// Mapping of the extended 256-color palette (`Color::Indexed(16..=255)`).
+    ///
+    /// Entry `i` is used for `Color::Indexed(16 + i)`.
+    /// If `None`, the standard xterm 6x6x6 color cube and grayscale ramp are used.
+    /// Indices `0..=15` always map to the ANSI colors of the theme.
+    pub extended_palette: Option<&'static [Rgb888; 240]>,
 }
 
 impl Default for ColorTheme {
@@ -73,6 +83,7 @@ impl ColorTheme {
             light_cyan: Rgb888::new(Rgb888::MAX_R / 2, Rgb888::MAX_G, Rgb888::MAX_B),
             gray: Rgb888::new(Rgb888::MAX_R / 2, Rgb888::MAX_G / 2, Rgb888::MAX_B / 2),
             dark_gray: Rgb888::new(170, 170, 170),
+            extended_palette: None,
         }
     }
 
@@ -97,10 +108,12 @@ impl ColorTheme {
             light_cyan: Rgb888::new(0x7d, 0xcf, 0xff), // Same as cyan
             gray: Rgb888::new(0xc0, 0xca, 0xf5),       // Light blue-white
             dark_gray: Rgb888::new(0x41, 0x48, 0x68),  // Dark blue-gray
+            extended_palette: None,
         }
     }
 
-    pub(crate) fn resolve(&self, color: Color, color_type: TermColorType) -> Rgb888 {
+    /// Resolves a Ratatui color in the given role to its RGB color in this theme.
+    pub fn resolve(&self, color: Color, color_type: TermColorType) -> Rgb888 {
         match color {
             Color::Reset => match color_type {
                 TermColorType::Foreground => self.foreground,
@@ -125,28 +138,116 @@ impl ColorTheme {
             Color::DarkGray => self.dark_gray,
 
             Color::Rgb(r, g, b) => Rgb888::new(r, g, b),
-            Color::Indexed(_) => Rgb888::BLACK,
+            Color::Indexed(index) => self.resolve_indexed(index),
+        }
+    }
+
+    fn resolve_indexed(&self, index: u8) -> Rgb888 {
+        match index {
+            0 => self.black,
+            1 => self.red,
+            2 => self.green,
+            3 => self.yellow,
+            4 => self.blue,
+            5 => self.magenta,
+            6 => self.cyan,
+            7 => self.gray,
+            8 => self.dark_gray,
+            9 => self.light_red,
+            10 => self.light_green,
+            11 => self.light_yellow,
+            12 => self.light_blue,
+            13 => self.light_magenta,
+            14 => self.light_cyan,
+            15 => self.white,
+            _ => match self.extended_palette {
+                Some(palette) => palette[index as usize - 16],
+                None => xterm_extended(index),
+            },
         }
     }
 }
 
-#[derive(Clone, Copy)]
+/// Standard xterm mapping of the extended palette (`16..=255`).
+const fn xterm_extended(index: u8) -> Rgb888 {
+    const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match index {
+        16..=231 => {
+            let cube = index - 16;
+            Rgb888::new(
+                CUBE_LEVELS[(cube / 36) as usize],
+                CUBE_LEVELS[(cube / 6 % 6) as usize],
+                CUBE_LEVELS[(cube % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * index.saturating_sub(232);
+            Rgb888::new(level, level, level)
+        }
+    }
+}
+
+/// Role of a color in a terminal cell.
+///
+/// `Color::Reset` resolves to a different color depending on the role.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TermColorType {
+    /// Text color, also used for underlines and strikethroughs.
     Foreground,
+    /// Cell background color.
     Background,
 }
 
 #[derive(Clone, Copy)]
-pub struct TermColor<'a>(pub Color, pub TermColorType, pub &'a ColorTheme);
+pub struct TermColor<'a>(
+    pub Color,
+    pub TermColorType,
+    pub &'a ColorTheme,
+    /// Dithering threshold of the pixel, see [`Dithering`](crate::Dithering).
+    pub Option<u8>,
+);
 
 impl<'a> TermColor<'a> {
     pub fn new(color: Color, color_type: TermColorType, theme: &'a ColorTheme) -> Self {
-        Self(color, color_type, theme)
+        Self(color, color_type, theme, None)
+    }
+
+    /// Returns the color of a pixel with the given dithering threshold.
+    pub fn dithered(self, threshold: u8) -> Self {
+        Self(self.0, self.1, self.2, Some(threshold))
     }
 
     fn to_rgb888(self) -> Rgb888 {
         self.2.resolve(self.0, self.1)
     }
+
+    /// Converts the color to the nearest ink of the palette, dithering it if a threshold is set.
+    #[cfg(any(feature = "epd-weact", feature = "epd-waveshare"))]
+    fn to_ink<C: embedded_graphics::pixelcolor::PixelColor>(
+        self,
+        ink_palette: &InkPalette<C>,
+    ) -> C {
+        match self.3 {
+            Some(threshold) => ink_palette.dithered(self.to_rgb888(), threshold),
+            None => ink_palette.nearest(self.to_rgb888()),
+        }
+    }
+
+    /// Converts the color to a monochrome color, dithering it if a threshold is set.
+    ///
+    /// Without a threshold, colors other than black and white are mapped by their role:
+    /// foreground to `On`, background to `Off`.
+    fn to_binary(self) -> BinaryColor {
+        match (self.to_rgb888(), self.3) {
+            (rgb, Some(threshold)) => (quantize(luma(rgb), 1, threshold) == 1).into(),
+            (rgb, None) if rgb == Rgb888::BLACK => BinaryColor::Off,
+            (rgb, None) if rgb == Rgb888::WHITE => BinaryColor::On,
+            (_, None) => match self.1 {
+                TermColorType::Foreground => BinaryColor::On,
+                TermColorType::Background => BinaryColor::Off,
+            },
+        }
+    }
 }
 
 macro_rules! impl_from_term_color {
@@ -163,16 +264,30 @@ macro_rules! impl_from_term_color {
 
 for_all_rgb_colors!(impl_from_term_color);
 
+macro_rules! impl_from_term_color_gray {
+    (
+        $color_type:ident
+    ) => {
+        impl<'a> From<TermColor<'a>> for $color_type {
+            fn from(color: TermColor<'a>) -> Self {
+                // Without dithering this matches the conversion of embedded-graphics, which uses
+                // the perceptual luma (0.299 * R + 0.587 * G + 0.114 * B) of the resolved color.
+                let luma = luma(color.to_rgb888());
+                let max_level = $color_type::WHITE.luma();
+                match color.3 {
+                    Some(threshold) => $color_type::new(quantize(luma, max_level, threshold)),
+                    None => Gray8::new(luma).into(),
+                }
+            }
+        }
+    };
+}
+
+for_all_gray_colors!(impl_from_term_color_gray);
+
 impl<'a> From<TermColor<'a>> for BinaryColor {
     fn from(color: TermColor<'a>) -> Self {
-        match color.to_rgb888() {
-            rgb if rgb == Rgb888::BLACK => BinaryColor::Off,
-            rgb if rgb == Rgb888::WHITE => BinaryColor::On,
-            _ => match color.1 {
-                TermColorType::Foreground => BinaryColor::On,
-                TermColorType::Background => BinaryColor::Off,
-            },
-        }
+        color.to_binary()
     }
 }
 
@@ -186,16 +301,7 @@ impl<'a> From<TermColor<'a>> for weact_studio_epd::Color {
 #[cfg(feature = "epd-weact")]
 impl<'a> From<TermColor<'a>> for weact_studio_epd::TriColor {
     fn from(color: TermColor<'a>) -> Self {
-        let rgb = color.to_rgb888();
-        match rgb {
-            rgb if rgb == Rgb888::WHITE => weact_studio_epd::TriColor::White,
-            rgb if rgb == Rgb888::BLACK => weact_studio_epd::TriColor::Black,
-            rgb if rgb == Rgb888::RED => weact_studio_epd::TriColor::Red,
-            _ => match color.1 {
-                TermColorType::Foreground => weact_studio_epd::TriColor::Black,
-                TermColorType::Background => weact_studio_epd::TriColor::White,
-            },
-        }
+        color.to_ink(&InkPalette::<Self>::DEFAULT)
     }
 }
 
@@ -212,14 +318,21 @@ impl From<TermColor<'_>> for epd_waveshare::color::Color {
 #[cfg(feature = "epd-waveshare")]
 impl From<TermColor<'_>> for epd_waveshare::color::TriColor {
     fn from(color: TermColor) -> Self {
-        match color.0 {
-            Color::White => epd_waveshare::color::TriColor::White,
-            Color::Black => epd_waveshare::color::TriColor::Black,
-            Color::Red => epd_waveshare::color::TriColor::Chromatic,
-            _ => match color.1 {
-                TermColorType::Foreground => epd_waveshare::color::TriColor::Black,
-                TermColorType::Background => epd_waveshare::color::TriColor::White,
-            },
+        color.to_ink(&InkPalette::<Self>::DEFAULT)
+    }
+}
+
+#[cfg(feature = "epd-waveshare")]
+impl From<TermColor<'_>> for epd_waveshare::color::OctColor {
+    fn from(color: TermColor) -> Self {
+        let rgb = color.to_rgb888();
+        let saturation = rgb.r().max(rgb.g()).max(rgb.b()) - rgb.r().min(rgb.g()).min(rgb.b());
+        // Map colors to the nearest ink of the same kind, so that colors without a matching ink
+        // (e.g. cyan or magenta) stay colored and grays don't turn orange.
+        if saturation >= 96 {
+            color.to_ink(&InkPalette::<Self>::CHROMATIC)
+        } else {
+            color.to_ink(&InkPalette::<Self>::ACHROMATIC)
         }
     }
 }
@@ -289,6 +402,73 @@ mod tests {
     }
     for_all_rgb_colors!(into_eg_color);
 
+    macro_rules! into_gray_color {
+        ($color_type:ident) => {
+            paste! {
+                #[rstest]
+                #[case(Foreground, Reset, &TEST_THEME, Gray8::WHITE)]
+                #[case(Background, Reset, &TEST_THEME, Gray8::BLACK)]
+                #[case(Foreground, White, &TEST_THEME, Gray8::WHITE)]
+                #[case(Background, Black, &TEST_THEME, Gray8::BLACK)]
+                #[case(Foreground, Red, &TEST_THEME, Gray8::new(77))]
+                #[case(Foreground, Green, &TEST_THEME, Gray8::new(149))]
+                #[case(Foreground, Blue, &TEST_THEME, Gray8::new(29))]
+                #[case(Background, Rgb(128, 128, 128), &TEST_THEME, Gray8::new(128))]
+                #[case(Background, Reset, &ColorTheme::tokyo_night(), Gray8::new(28))]
+                fn [<into_ $color_type:lower>] (
+                    #[case] color_type: TermColorType,
+                    #[case] color_from: Color,
+                    #[case] theme: &ColorTheme,
+                    #[case] color_into: Gray8,
+                ) {
+                    let output: $color_type = TermColor::new(color_from, color_type, theme).into();
+                    assert_eq!(output, $color_type::from(color_into));
+                }
+            }
+        };
+    }
+    for_all_gray_colors!(into_gray_color);
+
+    #[rstest]
+    #[case(Indexed(0), TEST_THEME.black)]
+    #[case(Indexed(1), TEST_THEME.red)]
+    #[case(Indexed(7), TEST_THEME.gray)]
+    #[case(Indexed(8), TEST_THEME.dark_gray)]
+    #[case(Indexed(9), TEST_THEME.light_red)]
+    #[case(Indexed(15), TEST_THEME.white)]
+    #[case(Indexed(16), Rgb888::new(0, 0, 0))]
+    #[case(Indexed(21), Rgb888::new(0, 0, 255))]
+    #[case(Indexed(67), Rgb888::new(95, 135, 175))]
+    #[case(Indexed(196), Rgb888::new(255, 0, 0))]
+    #[case(Indexed(231), Rgb888::new(255, 255, 255))]
+    #[case(Indexed(232), Rgb888::new(8, 8, 8))]
+    #[case(Indexed(244), Rgb888::new(128, 128, 128))]
+    #[case(Indexed(255), Rgb888::new(238, 238, 238))]
+    fn resolve_indexed(#[case] color: Color, #[case] expected: Rgb888) {
+        assert_eq!(TEST_THEME.resolve(color, Foreground), expected);
+    }
+
+    #[rstest]
+    fn resolve_indexed_with_extended_palette() {
+        static PALETTE: [Rgb888; 240] = {
+            let mut palette = [Rgb888::BLACK; 240];
+            palette[0] = Rgb888::new(1, 2, 3);
+            palette[239] = Rgb888::new(4, 5, 6);
+            palette
+        };
+        let theme = ColorTheme {
+            extended_palette: Some(&PALETTE),
+            ..ColorTheme::ansi()
+        };
+
+        assert_eq!(theme.resolve(Indexed(1), Foreground), theme.red);
+        assert_eq!(theme.resolve(Indexed(16), Foreground), Rgb888::new(1, 2, 3));
+        assert_eq!(
+            theme.resolve(Indexed(255), Foreground),
+            Rgb888::new(4, 5, 6)
+        );
+    }
+
     #[rstest]
     #[case(Foreground, Black, BinaryColor::Off)]
     #[case(Background, Black, BinaryColor::Off)]
@@ -328,6 +508,9 @@ mod tests {
     #[case(Background, White, weact_studio_epd::TriColor::White)]
     #[case(Foreground, Red, weact_studio_epd::TriColor::Red)]
     #[case(Background, Red, weact_studio_epd::TriColor::Red)]
+    #[case(Foreground, LightRed, weact_studio_epd::TriColor::Red)]
+    #[case(Background, Rgb(200, 40, 30), weact_studio_epd::TriColor::Red)]
+    #[case(Foreground, LightBlue, weact_studio_epd::TriColor::White)]
     fn into_weact_tricolor(
         #[case] color_type: TermColorType,
         #[case] color_from: Color,
@@ -360,6 +543,13 @@ mod tests {
     #[case(Background, White, epd_waveshare::color::TriColor::White)]
     #[case(Foreground, Red, epd_waveshare::color::TriColor::Chromatic)]
     #[case(Background, Red, epd_waveshare::color::TriColor::Chromatic)]
+    #[case(Foreground, LightRed, epd_waveshare::color::TriColor::Chromatic)]
+    #[case(
+        Background,
+        Rgb(200, 40, 30),
+        epd_waveshare::color::TriColor::Chromatic
+    )]
+    #[case(Foreground, Rgb(255, 255, 255), epd_waveshare::color::TriColor::White)]
     fn into_wavesharet_tricolor(
         #[case] color_type: TermColorType,
         #[case] color_from: Color,
@@ -368,4 +558,126 @@ mod tests {
         let output: epd_waveshare::color::TriColor = themed(color_type, color_from).into();
         assert_eq!(output, color_into);
     }
+
+    #[rstest]
+    #[case(Foreground, Black, 255, BinaryColor::Off)]
+    #[case(Background, White, 0, BinaryColor::On)]
+    #[case(Foreground, Rgb(128, 128, 128), 64, BinaryColor::On)]
+    #[case(Foreground, Rgb(128, 128, 128), 192, BinaryColor::Off)]
+    #[case(Background, Rgb(128, 128, 128), 64, BinaryColor::On)]
+    #[case(Background, Rgb(128, 128, 128), 192, BinaryColor::Off)]
+    fn into_dithered_binary_color(
+        #[case] color_type: TermColorType,
+        #[case] color_from: Color,
+        #[case] threshold: u8,
+        #[case] color_into: BinaryColor,
+    ) {
+        let output: BinaryColor = themed(color_type, color_from).dithered(threshold).into();
+        assert_eq!(output, color_into);
+    }
+
+    #[rstest]
+    #[case(White, 0, Gray2::WHITE)]
+    #[case(Black, 255, Gray2::BLACK)]
+    #[case(Rgb(128, 128, 128), 64, Gray2::new(2))]
+    #[case(Rgb(128, 128, 128), 192, Gray2::new(1))]
+    fn into_dithered_gray2(
+        #[case] color_from: Color,
+        #[case] threshold: u8,
+        #[case] color_into: Gray2,
+    ) {
+        let output: Gray2 = themed(Foreground, color_from).dithered(threshold).into();
+        assert_eq!(output, color_into);
+    }
+
+    #[rstest]
+    #[case(Rgb(128, 128, 128))]
+    #[case(Rgb(50, 100, 200))]
+    fn gray8_is_not_dithered(#[case] color_from: Color) {
+        let output: Gray8 = themed(Foreground, color_from).dithered(0).into();
+        assert_eq!(output, themed(Foreground, color_from).into());
+    }
+
+    #[cfg(feature = "epd-weact")]
+    #[rstest]
+    #[case(Red, 0, weact_studio_epd::TriColor::Red)]
+    #[case(Red, 255, weact_studio_epd::TriColor::Red)]
+    #[case(Rgb(128, 128, 128), 64, weact_studio_epd::TriColor::White)]
+    #[case(Rgb(128, 128, 128), 192, weact_studio_epd::TriColor::Black)]
+    fn into_dithered_weact_tricolor(
+        #[case] color_from: Color,
+        #[case] threshold: u8,
+        #[case] color_into: weact_studio_epd::TriColor,
+    ) {
+        let output: weact_studio_epd::TriColor =
+            themed(Foreground, color_from).dithered(threshold).into();
+        assert_eq!(output, color_into);
+    }
+
+    #[cfg(feature = "epd-waveshare")]
+    #[rstest]
+    #[case(Red, 0, epd_waveshare::color::TriColor::Chromatic)]
+    #[case(Red, 255, epd_waveshare::color::TriColor::Chromatic)]
+    #[case(Rgb(128, 128, 128), 64, epd_waveshare::color::TriColor::White)]
+    #[case(Rgb(128, 128, 128), 192, epd_waveshare::color::TriColor::Black)]
+    fn into_dithered_waveshare_tricolor(
+        #[case] color_from: Color,
+        #[case] threshold: u8,
+        #[case] color_into: epd_waveshare::color::TriColor,
+    ) {
+        let output: epd_waveshare::color::TriColor =
+            themed(Foreground, color_from).dithered(threshold).into();
+        assert_eq!(output, color_into);
+    }
+
+    #[cfg(feature = "epd-waveshare")]
+    #[rstest]
+    #[case(Foreground, Reset, epd_waveshare::color::OctColor::White)]
+    #[case(Background, Reset, epd_waveshare::color::OctColor::Black)]
+    #[case(Foreground, Black, epd_waveshare::color::OctColor::Black)]
+    #[case(Foreground, White, epd_waveshare::color::OctColor::White)]
+    #[case(Foreground, Red, epd_waveshare::color::OctColor::Red)]
+    #[case(Foreground, Green, epd_waveshare::color::OctColor::Green)]
+    #[case(Foreground, Blue, epd_waveshare::color::OctColor::Blue)]
+    #[case(Foreground, Yellow, epd_waveshare::color::OctColor::Yellow)]
+    #[case(Foreground, Magenta, epd_waveshare::color::OctColor::Red)]
+    #[case(Foreground, Cyan, epd_waveshare::color::OctColor::Green)]
+    #[case(Foreground, LightRed, epd_waveshare::color::OctColor::Orange)]
+    #[case(Foreground, LightBlue, epd_waveshare::color::OctColor::Blue)]
+    #[case(Foreground, Gray, epd_waveshare::color::OctColor::Black)]
+    #[case(Foreground, DarkGray, epd_waveshare::color::OctColor::White)]
+    #[case(Foreground, Rgb(255, 128, 0), epd_waveshare::color::OctColor::Orange)]
+    #[case(Background, Rgb(250, 240, 230), epd_waveshare::color::OctColor::White)]
+    fn into_waveshare_octcolor(
+        #[case] color_type: TermColorType,
+        #[case] color_from: Color,
+        #[case] color_into: epd_waveshare::color::OctColor,
+    ) {
+        let output: epd_waveshare::color::OctColor = themed(color_type, color_from).into();
+        assert_eq!(output, color_into);
+    }
+
+    #[cfg(feature = "epd-waveshare")]
+    #[rstest]
+    fn waveshare_octcolor_themed() {
+        let theme = ColorTheme::tokyo_night();
+        let output: epd_waveshare::color::OctColor =
+            TermColor::new(Color::Green, Foreground, &theme).into();
+        assert_eq!(output, epd_waveshare::color::OctColor::Green);
+    }
+
+    #[cfg(feature = "epd-waveshare")]
+    #[rstest]
+    #[case(Gray, 64, epd_waveshare::color::OctColor::White)]
+    #[case(Gray, 192, epd_waveshare::color::OctColor::Black)]
+    #[case(Red, 255, epd_waveshare::color::OctColor::Red)]
+    fn into_dithered_waveshare_octcolor(
+        #[case] color_from: Color,
+        #[case] threshold: u8,
+        #[case] color_into: epd_waveshare::color::OctColor,
+    ) {
+        let output: epd_waveshare::color::OctColor =
+            themed(Foreground, color_from).dithered(threshold).into();
+        assert_eq!(output, color_into);
+    }
 }
@@ -0,0 +1,133 @@
+//! Ordered dithering for monochrome and low color depth displays.
+
+use embedded_graphics::geometry::Point;
+use embedded_graphics::pixelcolor::{Gray8, GrayColor, Rgb888};
+
+/// Ordered dithering pattern.
+///
+/// Dithering approximates colors which the display can't show (e.g. `Color::DarkGray` on a
+/// monochrome display) with a pattern of the colors it can show.
+/// It is applied to `BinaryColor`, `Gray2`, `Gray4` and EPD colors; other colors are unaffected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dithering {
+    /// 2x2 Bayer matrix, 5 levels per color step.
+    Bayer2x2,
+    /// 4x4 Bayer matrix, 17 levels per color step.
+    Bayer4x4,
+}
+
+impl Dithering {
+    /// Returns the threshold (`1..=255`) of the pattern at `point`.
+    pub(crate) fn threshold(self, point: Point) -> u8 {
+        const BAYER_2X2: [[u8; 2]; 2] = [[0, 2], [3, 1]];
+        const BAYER_4X4: [[u8; 4]; 4] =
+            [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+        let (x, y) = (
+            point.x.rem_euclid(4) as usize,
+            point.y.rem_euclid(4) as usize,
+        );
+        let (index, levels) = match self {
+            Dithering::Bayer2x2 => (BAYER_2X2[y % 2][x % 2], 4),
+            Dithering::Bayer4x4 => (BAYER_4X4[y][x], 16),
+        };
+        // center of the matrix cell, scaled to 0..256
+        ((2 * index as u16 + 1) * 128 / levels) as u8
+    }
+}
+
+/// Perceptual luma of a color, as converted to `Gray8` by embedded-graphics.
+pub(crate) fn luma(color: Rgb888) -> u8 {
+    Gray8::from(color).luma()
+}
+
+/// Quantizes `luma` to a level in `0..=max_level` using the dithering `threshold`.
+pub(crate) fn quantize(luma: u8, max_level: u8, threshold: u8) -> u8 {
+    let scaled = luma as u16 * max_level as u16;
+    let level = (scaled / 255) as u8;
+    // position between `level` and the next level, scaled to 0..256
+    let fraction = (scaled % 255) * 256 / 255;
+    if fraction > threshold as u16 {
+        level + 1
+    } else {
+        level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::pixelcolor::RgbColor;
+    use rstest::rstest;
+
+    fn pattern(dithering: Dithering, size: i32, luma: u8, max_level: u8) -> alloc::vec::Vec<u8> {
+        let mut pattern = alloc::vec::Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                let threshold = dithering.threshold(Point::new(x, y));
+                pattern.push(quantize(luma, max_level, threshold));
+            }
+        }
+        pattern
+    }
+
+    #[rstest]
+    #[case(Dithering::Bayer2x2, 2)]
+    #[case(Dithering::Bayer4x4, 4)]
+    fn solid_colors_are_not_dithered(#[case] dithering: Dithering, #[case] size: i32) {
+        for max_level in [1, 3, 15] {
+            assert!(
+                pattern(dithering, size, 0, max_level)
+                    .iter()
+                    .all(|&l| l == 0)
+            );
+            assert!(
+                pattern(dithering, size, 255, max_level)
+                    .iter()
+                    .all(|&l| l == max_level)
+            );
+        }
+    }
+
+    #[rstest]
+    #[case(Dithering::Bayer2x2, 2, 128, 2)]
+    #[case(Dithering::Bayer2x2, 2, 64, 1)]
+    #[case(Dithering::Bayer4x4, 4, 128, 8)]
+    #[case(Dithering::Bayer4x4, 4, 170, 11)]
+    fn dithers_to_matching_density(
+        #[case] dithering: Dithering,
+        #[case] size: i32,
+        #[case] luma: u8,
+        #[case] pixels_on: usize,
+    ) {
+        let pattern = pattern(dithering, size, luma, 1);
+        assert_eq!(pattern.iter().filter(|&&l| l == 1).count(), pixels_on);
+    }
+
+    #[rstest]
+    fn dithers_between_gray_levels() {
+        // halfway between the levels 1 (85) and 2 (170) of Gray2
+        let pattern = pattern(Dithering::Bayer2x2, 2, 128, 3);
+        assert!(pattern.iter().all(|&l| l == 1 || l == 2));
+        assert_eq!(pattern.iter().filter(|&&l| l == 2).count(), 2);
+    }
+
+    #[rstest]
+    fn does_not_dither_full_depth() {
+        for luma in [0, 1, 127, 200, 255] {
+            assert!(
+                pattern(Dithering::Bayer4x4, 4, luma, 255)
+                    .iter()
+                    .all(|&l| l == luma)
+            );
+        }
+    }
+
+    #[rstest]
+    #[case(Rgb888::BLACK, 0)]
+    #[case(Rgb888::WHITE, 255)]
+    #[case(Rgb888::RED, 77)]
+    fn computes_luma(#[case] color: Rgb888, #[case] expected: u8) {
+        assert_eq!(luma(color), expected);
+    }
+}
@@ -0,0 +1,89 @@
+//! E-paper displays driven directly by the backend.
+//!
+//! An [`EpdDisplay`] owns the driver of the panel together with its frame buffer, so it can be
+//! used as the draw target of [`EmbeddedBackend`](crate::EmbeddedBackend) and refreshed from
+//! the [`epd_flush_callback`] without any driver code in the application.
+
+use alloc::boxed::Box;
+use core::fmt::Debug;
+use core::time::Duration;
+
+use embedded_graphics::draw_target::DrawTarget;
+
+use crate::backend::FlushCallback;
+use crate::error::{DrawTargetError, EpdRefreshError};
+use crate::refresh_policy::{Refresh, RefreshPolicy};
+
+/// An e-paper display with its driver and frame buffer.
+///
+/// Drawing goes to the frame buffer, which is sent to the panel by the refresh methods.
+/// Implemented by [`WeActEpd`](crate::WeActEpd) and [`WaveshareEpd`](crate::WaveshareEpd).
+pub trait EpdDisplay: DrawTarget {
+    /// Error returned by the driver.
+    type DriverError: Debug;
+
+    /// Sends the frame buffer to the panel and refreshes the whole panel.
+    fn full_refresh(&mut self) -> Result<(), Self::DriverError>;
+
+    /// Sends the frame buffer to the panel and refreshes only the changed pixels.
+    ///
+    /// Panels without partial refresh do a full refresh.
+    fn partial_refresh(&mut self) -> Result<(), Self::DriverError>;
+
+    /// Puts the panel into deep sleep.
+    fn sleep(&mut self) -> Result<(), Self::DriverError>;
+
+    /// Wakes the panel up from deep sleep.
+    ///
+    /// The next refresh is a full refresh.
+    fn wake_up(&mut self) -> Result<(), Self::DriverError>;
+
+    /// Returns the policy choosing between full and partial refreshes.
+    fn refresh_policy(&mut self) -> &mut RefreshPolicy;
+
+    /// Does the given refresh. A [`Refresh::Deferred`] does nothing.
+    fn refresh(&mut self, refresh: Refresh) -> Result<(), Self::DriverError> {
+        match refresh {
+            Refresh::Full => self.full_refresh(),
+            Refresh::Partial => self.partial_refresh(),
+            Refresh::Deferred => Ok(()),
+        }
+    }
+
+    /// Refreshes the panel as chosen by the [refresh policy](Self::refresh_policy) for a flush at
+    /// `now`, returning the chosen refresh.
+    fn flush(&mut self, now: Duration) -> Result<Refresh, Self::DriverError> {
+        let refresh = self.refresh_policy().on_flush(now);
+        self.refresh(refresh)?;
+        Ok(refresh)
+    }
+
+    /// Does the refresh of a deferred flush once it is due, see [`RefreshPolicy::poll`].
+    fn poll(&mut self, now: Duration) -> Result<Option<Refresh>, Self::DriverError> {
+        let refresh = self.refresh_policy().poll(now);
+        if let Some(refresh) = refresh {
+            self.refresh(refresh)?;
+        }
+        Ok(refresh)
+    }
+}
+
+/// Returns a flush callback which refreshes an [`EpdDisplay`] with [`EpdDisplay::flush`].
+///
+/// `clock` returns the current time, as the duration since any fixed point
+/// (e.g. the boot of the device).
+///
+/// A driver error fails the flush with [`Error::FlushError`](crate::error::Error::FlushError),
+/// holding an [`EpdRefreshError`].
+pub fn epd_flush_callback<E>(mut clock: impl FnMut() -> Duration + 'static) -> FlushCallback<E>
+where
+    E: EpdDisplay,
+    E::DriverError: Send + Sync + 'static,
+{
+    Box::new(move |display: &mut E| {
+        display
+            .flush(clock())
+            .map_err(|error| EpdRefreshError(DrawTargetError(error)))?;
+        Ok(())
+    })
+}
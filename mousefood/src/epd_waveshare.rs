@@ -0,0 +1,436 @@
+//! [`EpdDisplay`] for the displays of the `epd-waveshare` driver.
+
+use core::marker::PhantomData;
+
+use embedded_graphics::Pixel;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{OriginDimensions, Size};
+use embedded_graphics::pixelcolor::PixelColor;
+use embedded_graphics::primitives::Rectangle;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::SpiDevice;
+use epd_waveshare::color::ColorType;
+use epd_waveshare::graphics::Display;
+use epd_waveshare::prelude::{RefreshLut, WaveshareDisplay};
+
+use crate::epd::EpdDisplay;
+use crate::refresh_policy::RefreshPolicy;
+
+/// A Waveshare e-paper display, owning the driver, the SPI device, the delay and the frame
+/// buffer.
+///
+/// Partial refreshes use the quick lookup table of the driver ([`RefreshLut::Quick`]);
+/// drivers without one do full refreshes instead.
+///
+/// ```rust,ignore
+/// use epd_waveshare::{epd2in9::*, prelude::*};
+/// use mousefood::prelude::*;
+/// use mousefood::{RefreshPolicy, WaveshareEpd, epd_flush_callback};
+///
+/// let epd = Epd2in9::new(&mut spi_device, busy, dc, rst, &mut delay, None)?;
+/// let mut display = WaveshareEpd::new(
+///     epd,
+///     Display2in9::default(),
+///     spi_device,
+///     delay,
+///     RefreshPolicy::new().full_every(20),
+/// );
+/// let config = EmbeddedBackendConfig {
+///     flush_callback: epd_flush_callback(uptime),
+///     ..Default::default()
+/// };
+/// let backend = EmbeddedBackend::new(&mut display, config);
+/// ```
+pub struct WaveshareEpd<
+    SPI,
+    BUSY,
+    DC,
+    RST,
+    DELAY,
+    EPD,
+    const WIDTH: u32,
+    const HEIGHT: u32,
+    const BWRBIT: bool,
+    const BYTECOUNT: usize,
+    COLOR: ColorType + PixelColor,
+> {
+    epd: EPD,
+    display: Display<WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR>,
+    spi: SPI,
+    delay: DELAY,
+    refresh_policy: RefreshPolicy,
+    /// Lookup table loaded into the driver.
+    lut: RefreshLut,
+    pins: PhantomData<(BUSY, DC, RST)>,
+}
+
+impl<
+    SPI,
+    BUSY,
+    DC,
+    RST,
+    DELAY,
+    EPD,
+    const WIDTH: u32,
+    const HEIGHT: u32,
+    const BWRBIT: bool,
+    const BYTECOUNT: usize,
+    COLOR,
+> WaveshareEpd<SPI, BUSY, DC, RST, DELAY, EPD, WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+    EPD: WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>,
+    COLOR: ColorType + PixelColor,
+{
+    /// Creates the display from an initialized driver and its frame buffer.
+    pub fn new(
+        epd: EPD,
+        display: Display<WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR>,
+        spi: SPI,
+        delay: DELAY,
+        refresh_policy: RefreshPolicy,
+    ) -> Self {
+        Self {
+            epd,
+            display,
+            spi,
+            delay,
+            refresh_policy,
+            lut: RefreshLut::Full,
+            pins: PhantomData,
+        }
+    }
+
+    /// Returns the frame buffer.
+    pub fn display(&self) -> &Display<WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR> {
+        &self.display
+    }
+
+    /// Returns the frame buffer mutably, e.g. to change its rotation.
+    pub fn display_mut(&mut self) -> &mut Display<WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR> {
+        &mut self.display
+    }
+
+    /// Returns the driver, e.g. for operations not covered by [`EpdDisplay`].
+    pub fn epd_mut(&mut self) -> &mut EPD {
+        &mut self.epd
+    }
+
+    /// Loads `lut` into the driver unless it is already loaded, then refreshes the panel.
+    fn refresh_with(&mut self, lut: RefreshLut) -> Result<(), SPI::Error> {
+        if self.lut != lut {
+            self.epd
+                .set_lut(&mut self.spi, &mut self.delay, Some(lut))?;
+            self.lut = lut;
+        }
+        self.epd
+            .update_and_display_frame(&mut self.spi, self.display.buffer(), &mut self.delay)
+    }
+}
+
+impl<
+    SPI,
+    BUSY,
+    DC,
+    RST,
+    DELAY,
+    EPD,
+    const WIDTH: u32,
+    const HEIGHT: u32,
+    const BWRBIT: bool,
+    const BYTECOUNT: usize,
+    COLOR,
+> EpdDisplay
+    for WaveshareEpd<SPI, BUSY, DC, RST, DELAY, EPD, WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR>
+where
+    SPI: SpiDevice,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayNs,
+    EPD: WaveshareDisplay<SPI, BUSY, DC, RST, DELAY>,
+    COLOR: ColorType + PixelColor,
+{
+    type DriverError = SPI::Error;
+
+    fn full_refresh(&mut self) -> Result<(), Self::DriverError> {
+        self.refresh_with(RefreshLut::Full)
+    }
+
+    fn partial_refresh(&mut self) -> Result<(), Self::DriverError> {
+        self.refresh_with(RefreshLut::Quick)
+    }
+
+    fn sleep(&mut self) -> Result<(), Self::DriverError> {
+        self.epd.sleep(&mut self.spi, &mut self.delay)
+    }
+
+    fn wake_up(&mut self) -> Result<(), Self::DriverError> {
+        self.epd.wake_up(&mut self.spi, &mut self.delay)?;
+        // waking up initializes the driver, which loads the full lookup table
+        self.lut = RefreshLut::Full;
+        self.refresh_policy.request_full();
+        Ok(())
+    }
+
+    fn refresh_policy(&mut self) -> &mut RefreshPolicy {
+        &mut self.refresh_policy
+    }
+}
+
+impl<
+    SPI,
+    BUSY,
+    DC,
+    RST,
+    DELAY,
+    EPD,
+    const WIDTH: u32,
+    const HEIGHT: u32,
+    const BWRBIT: bool,
+    const BYTECOUNT: usize,
+    COLOR,
+> DrawTarget
+    for WaveshareEpd<SPI, BUSY, DC, RST, DELAY, EPD, WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR>
+where
+    COLOR: ColorType + PixelColor,
+{
+    type Color = COLOR;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.display.draw_iter(pixels)
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.display.fill_contiguous(area, colors)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.display.fill_solid(area, color)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.display.clear(color)
+    }
+}
+
+impl<
+    SPI,
+    BUSY,
+    DC,
+    RST,
+    DELAY,
+    EPD,
+    const WIDTH: u32,
+    const HEIGHT: u32,
+    const BWRBIT: bool,
+    const BYTECOUNT: usize,
+    COLOR,
+> OriginDimensions
+    for WaveshareEpd<SPI, BUSY, DC, RST, DELAY, EPD, WIDTH, HEIGHT, BWRBIT, BYTECOUNT, COLOR>
+where
+    COLOR: ColorType + PixelColor,
+{
+    fn size(&self) -> Size {
+        self.display.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+    use core::convert::Infallible;
+    use core::time::Duration;
+
+    use embedded_hal::spi::Operation;
+    use epd_waveshare::color::Color;
+    use epd_waveshare::epd2in9::{Display2in9, Epd2in9};
+
+    use crate::epd_mocks::{MockDelay, MockPin};
+    use crate::refresh_policy::Refresh;
+
+    const WRITE_RAM: u8 = 0x24;
+    const WRITE_LUT_REGISTER: u8 = 0x32;
+    const DEEP_SLEEP_MODE: u8 = 0x10;
+
+    /// Commands sent to the panel, with their data.
+    type Log = Rc<RefCell<Vec<(u8, Vec<u8>)>>>;
+
+    /// SPI device recording the bytes sent to the panel.
+    struct MockSpi {
+        log: Log,
+        /// Whether the data/command pin is set to data.
+        data: Rc<RefCell<bool>>,
+    }
+
+    impl embedded_hal::spi::ErrorType for MockSpi {
+        type Error = Infallible;
+    }
+
+    impl SpiDevice for MockSpi {
+        fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+            let mut log = self.log.borrow_mut();
+            for operation in operations {
+                if let Operation::Write(bytes) = operation {
+                    if *self.data.borrow() {
+                        log.last_mut().unwrap().1.extend_from_slice(bytes);
+                    } else {
+                        log.extend(bytes.iter().map(|&command| (command, Vec::new())));
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Data/command pin of the panel.
+    struct MockDc(Rc<RefCell<bool>>);
+
+    impl embedded_hal::digital::ErrorType for MockDc {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for MockDc {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            *self.0.borrow_mut() = false;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            *self.0.borrow_mut() = true;
+            Ok(())
+        }
+    }
+
+    type MockEpd = WaveshareEpd<
+        MockSpi,
+        MockPin,
+        MockDc,
+        MockPin,
+        MockDelay,
+        Epd2in9<MockSpi, MockPin, MockDc, MockPin, MockDelay>,
+        128,
+        296,
+        false,
+        { epd_waveshare::buffer_len(128, 296) },
+        Color,
+    >;
+
+    /// Creates a display and the log of its commands, cleared after the initialization.
+    fn epd(refresh_policy: RefreshPolicy) -> (MockEpd, Log) {
+        let log = Log::default();
+        let data = Rc::new(RefCell::new(false));
+        let mut spi = MockSpi {
+            log: log.clone(),
+            data: data.clone(),
+        };
+        let epd = Epd2in9::new(
+            &mut spi,
+            MockPin,
+            MockDc(data),
+            MockPin,
+            &mut MockDelay,
+            None,
+        )
+        .unwrap();
+        log.borrow_mut().clear();
+        let display =
+            WaveshareEpd::new(epd, Display2in9::default(), spi, MockDelay, refresh_policy);
+        (display, log)
+    }
+
+    fn commands(log: &Log) -> Vec<u8> {
+        log.borrow_mut()
+            .drain(..)
+            .map(|(command, _)| command)
+            .collect()
+    }
+
+    #[rstest]
+    fn sends_frame_buffer() {
+        let (mut epd, log) = epd(RefreshPolicy::new());
+        epd.fill_solid(
+            &Rectangle::new(embedded_graphics::geometry::Point::zero(), Size::new(8, 1)),
+            Color::Black,
+        )
+        .unwrap();
+        epd.full_refresh().unwrap();
+
+        let log = log.borrow();
+        let (_, buffer) = log
+            .iter()
+            .find(|(command, _)| *command == WRITE_RAM)
+            .unwrap();
+        assert_eq!(buffer.as_slice(), epd.display().buffer());
+    }
+
+    #[rstest]
+    fn switches_lookup_table() {
+        let (mut epd, log) = epd(RefreshPolicy::new());
+
+        epd.full_refresh().unwrap();
+        assert!(!commands(&log).contains(&WRITE_LUT_REGISTER));
+
+        epd.partial_refresh().unwrap();
+        let commands_ = commands(&log);
+        assert!(commands_.contains(&WRITE_LUT_REGISTER));
+        assert!(commands_.contains(&WRITE_RAM));
+
+        epd.partial_refresh().unwrap();
+        assert!(!commands(&log).contains(&WRITE_LUT_REGISTER));
+
+        epd.full_refresh().unwrap();
+        assert!(commands(&log).contains(&WRITE_LUT_REGISTER));
+    }
+
+    #[rstest]
+    fn sleeps_and_wakes_up() {
+        let (mut epd, log) = epd(RefreshPolicy::new());
+        assert_eq!(epd.flush(Duration::ZERO).unwrap(), Refresh::Full);
+        assert_eq!(epd.flush(Duration::from_secs(1)).unwrap(), Refresh::Partial);
+        log.borrow_mut().clear();
+
+        epd.sleep().unwrap();
+        assert_eq!(commands(&log), [DEEP_SLEEP_MODE]);
+
+        epd.wake_up().unwrap();
+        assert!(!commands(&log).is_empty());
+        assert_eq!(epd.flush(Duration::from_secs(2)).unwrap(), Refresh::Full);
+        assert_eq!(epd.flush(Duration::from_secs(3)).unwrap(), Refresh::Partial);
+    }
+
+    #[rstest]
+    fn flushes_through_backend() {
+        use crate::{EmbeddedBackend, EmbeddedBackendConfig};
+        use ratatui::Terminal;
+
+        let (mut epd, log) = epd(RefreshPolicy::new());
+        let config = EmbeddedBackendConfig {
+            flush_callback: crate::epd::epd_flush_callback(|| Duration::ZERO),
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(EmbeddedBackend::new(&mut epd, config)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget("mousefood", frame.area()))
+            .unwrap();
+
+        assert!(commands(&log).contains(&WRITE_RAM));
+    }
+}
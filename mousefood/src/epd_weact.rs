@@ -0,0 +1,443 @@
+//! [`EpdDisplay`] for the displays of the `weact-studio-epd` driver.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use embedded_graphics::Pixel;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{OriginDimensions, Size};
+use embedded_graphics::primitives::Rectangle;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+use weact_studio_epd::graphics::Display;
+use weact_studio_epd::{Color, DisplayDriver, TriColor};
+
+use crate::epd::EpdDisplay;
+use crate::refresh_policy::RefreshPolicy;
+
+/// A WeAct Studio e-paper display, owning the driver (with its delay) and the frame buffer.
+///
+/// Black and white panels support partial refreshes, tri-color panels always do full refreshes.
+///
+/// ```rust,ignore
+/// use mousefood::prelude::*;
+/// use mousefood::{RefreshPolicy, WeActEpd, epd_flush_callback};
+/// use weact_studio_epd::WeActStudio290BlackWhiteDriver;
+/// use weact_studio_epd::graphics::Display290BlackWhite;
+///
+/// let mut driver = WeActStudio290BlackWhiteDriver::new(spi_interface, busy, rst, delay);
+/// driver.init()?;
+/// let mut display = WeActEpd::new(
+///     driver,
+///     Display290BlackWhite::new(),
+///     RefreshPolicy::new().full_every(20),
+/// );
+/// let config = EmbeddedBackendConfig {
+///     flush_callback: epd_flush_callback(uptime),
+///     ..Default::default()
+/// };
+/// let backend = EmbeddedBackend::new(&mut display, config);
+/// ```
+pub struct WeActEpd<
+    DI,
+    BSY,
+    RST,
+    DELAY,
+    const WIDTH: u32,
+    const VISIBLE_WIDTH: u32,
+    const HEIGHT: u32,
+    const BUFFER_SIZE: usize,
+    C,
+> {
+    driver: DisplayDriver<DI, BSY, RST, DELAY, WIDTH, VISIBLE_WIDTH, HEIGHT, C>,
+    display: Display<WIDTH, HEIGHT, BUFFER_SIZE, C>,
+    refresh_policy: RefreshPolicy,
+}
+
+impl<
+    DI,
+    BSY,
+    RST,
+    DELAY,
+    const WIDTH: u32,
+    const VISIBLE_WIDTH: u32,
+    const HEIGHT: u32,
+    const BUFFER_SIZE: usize,
+    C,
+> WeActEpd<DI, BSY, RST, DELAY, WIDTH, VISIBLE_WIDTH, HEIGHT, BUFFER_SIZE, C>
+{
+    /// Creates the display from an initialized driver and its frame buffer.
+    pub fn new(
+        driver: DisplayDriver<DI, BSY, RST, DELAY, WIDTH, VISIBLE_WIDTH, HEIGHT, C>,
+        display: Display<WIDTH, HEIGHT, BUFFER_SIZE, C>,
+        refresh_policy: RefreshPolicy,
+    ) -> Self {
+        Self {
+            driver,
+            display,
+            refresh_policy,
+        }
+    }
+
+    /// Returns the frame buffer.
+    pub fn display(&self) -> &Display<WIDTH, HEIGHT, BUFFER_SIZE, C> {
+        &self.display
+    }
+
+    /// Returns the frame buffer mutably, e.g. to change its rotation.
+    pub fn display_mut(&mut self) -> &mut Display<WIDTH, HEIGHT, BUFFER_SIZE, C> {
+        &mut self.display
+    }
+
+    /// Returns the driver, e.g. for operations not covered by [`EpdDisplay`].
+    pub fn driver_mut(
+        &mut self,
+    ) -> &mut DisplayDriver<DI, BSY, RST, DELAY, WIDTH, VISIBLE_WIDTH, HEIGHT, C> {
+        &mut self.driver
+    }
+}
+
+/// Implements [`EpdDisplay`] and [`DrawTarget`] for a color of the driver, refreshing partially
+/// with `$partial_update`.
+macro_rules! impl_epd_display {
+    ($color:ident, $partial_update:ident) => {
+        impl<
+            DI,
+            BSY,
+            RST,
+            DELAY,
+            const WIDTH: u32,
+            const VISIBLE_WIDTH: u32,
+            const HEIGHT: u32,
+            const BUFFER_SIZE: usize,
+        > EpdDisplay
+            for WeActEpd<DI, BSY, RST, DELAY, WIDTH, VISIBLE_WIDTH, HEIGHT, BUFFER_SIZE, $color>
+        where
+            DI: WriteOnlyDataCommand,
+            BSY: InputPin,
+            RST: OutputPin,
+            DELAY: DelayNs,
+        {
+            type DriverError = DisplayError;
+
+            fn full_refresh(&mut self) -> Result<(), Self::DriverError> {
+                self.driver.full_update(&self.display)
+            }
+
+            fn partial_refresh(&mut self) -> Result<(), Self::DriverError> {
+                self.driver.$partial_update(&self.display)
+            }
+
+            fn sleep(&mut self) -> Result<(), Self::DriverError> {
+                self.driver.sleep()
+            }
+
+            fn wake_up(&mut self) -> Result<(), Self::DriverError> {
+                self.driver.wake_up()?;
+                // the reset clears the lookup table of partial refreshes
+                self.refresh_policy.request_full();
+                Ok(())
+            }
+
+            fn refresh_policy(&mut self) -> &mut RefreshPolicy {
+                &mut self.refresh_policy
+            }
+        }
+
+        impl<
+            DI,
+            BSY,
+            RST,
+            DELAY,
+            const WIDTH: u32,
+            const VISIBLE_WIDTH: u32,
+            const HEIGHT: u32,
+            const BUFFER_SIZE: usize,
+        > DrawTarget
+            for WeActEpd<DI, BSY, RST, DELAY, WIDTH, VISIBLE_WIDTH, HEIGHT, BUFFER_SIZE, $color>
+        {
+            type Color = $color;
+            type Error = core::convert::Infallible;
+
+            fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+            where
+                I: IntoIterator<Item = Pixel<Self::Color>>,
+            {
+                self.display.draw_iter(pixels)
+            }
+
+            fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+            where
+                I: IntoIterator<Item = Self::Color>,
+            {
+                self.display.fill_contiguous(area, colors)
+            }
+
+            fn fill_solid(
+                &mut self,
+                area: &Rectangle,
+                color: Self::Color,
+            ) -> Result<(), Self::Error> {
+                self.display.fill_solid(area, color)
+            }
+
+            fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+                self.display.clear(color);
+                Ok(())
+            }
+        }
+
+        impl<
+            DI,
+            BSY,
+            RST,
+            DELAY,
+            const WIDTH: u32,
+            const VISIBLE_WIDTH: u32,
+            const HEIGHT: u32,
+            const BUFFER_SIZE: usize,
+        > OriginDimensions
+            for WeActEpd<DI, BSY, RST, DELAY, WIDTH, VISIBLE_WIDTH, HEIGHT, BUFFER_SIZE, $color>
+        {
+            fn size(&self) -> Size {
+                self.display.size()
+            }
+        }
+    };
+}
+
+impl_epd_display!(Color, fast_update);
+impl_epd_display!(TriColor, full_update);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+    use core::time::Duration;
+
+    use display_interface::DataFormat;
+    use weact_studio_epd::graphics::{Display290BlackWhite, Display290TriColor};
+    use weact_studio_epd::{WeActStudio290BlackWhiteDriver, WeActStudio290TriColorDriver};
+
+    use crate::epd_mocks::{MockDelay, MockPin};
+    use crate::refresh_policy::Refresh;
+
+    const DEEP_SLEEP: u8 = 0x10;
+    const MASTER_ACTIVATE: u8 = 0x20;
+    const UPDATE_DISPLAY_CTRL2: u8 = 0x22;
+    const WRITE_BW_DATA: u8 = 0x24;
+    const WRITE_LUT: u8 = 0x32;
+    /// Display mode of [`UPDATE_DISPLAY_CTRL2`] for full refreshes.
+    const DISPLAY_MODE_1: u8 = 0xF7;
+
+    /// Commands sent to the panel, with their data.
+    type Log = Rc<RefCell<Vec<(u8, Vec<u8>)>>>;
+
+    /// Display interface recording the bytes sent to the panel.
+    struct MockInterface(Log);
+
+    impl WriteOnlyDataCommand for MockInterface {
+        fn send_commands(&mut self, commands: DataFormat<'_>) -> Result<(), DisplayError> {
+            let DataFormat::U8(commands) = commands else {
+                return Err(DisplayError::DataFormatNotImplemented);
+            };
+            let mut log = self.0.borrow_mut();
+            log.extend(commands.iter().map(|&command| (command, Vec::new())));
+            Ok(())
+        }
+
+        fn send_data(&mut self, data: DataFormat<'_>) -> Result<(), DisplayError> {
+            let mut log = self.0.borrow_mut();
+            let (_, bytes) = log.last_mut().ok_or(DisplayError::InvalidFormatError)?;
+            match data {
+                DataFormat::U8(data) => bytes.extend_from_slice(data),
+                DataFormat::U8Iter(data) => bytes.extend(data),
+                _ => return Err(DisplayError::DataFormatNotImplemented),
+            }
+            Ok(())
+        }
+    }
+
+    fn black_white_epd(
+        refresh_policy: RefreshPolicy,
+    ) -> (
+        WeActEpd<
+            MockInterface,
+            MockPin,
+            MockPin,
+            MockDelay,
+            128,
+            128,
+            296,
+            { weact_studio_epd::graphics::buffer_len::<Color>(128, 296) },
+            Color,
+        >,
+        Log,
+    ) {
+        let log = Log::default();
+        let mut driver = WeActStudio290BlackWhiteDriver::new(
+            MockInterface(log.clone()),
+            MockPin,
+            MockPin,
+            MockDelay,
+        );
+        driver.init().unwrap();
+        log.borrow_mut().clear();
+        (
+            WeActEpd::new(driver, Display290BlackWhite::new(), refresh_policy),
+            log,
+        )
+    }
+
+    /// Returns the display modes of the refreshes in the log and clears it.
+    fn refreshes(log: &Log) -> Vec<u8> {
+        let mut log = log.borrow_mut();
+        let refreshes = log
+            .windows(2)
+            .filter(|pair| pair[0].0 == UPDATE_DISPLAY_CTRL2 && pair[1].0 == MASTER_ACTIVATE)
+            .map(|pair| pair[0].1[0])
+            .collect();
+        log.clear();
+        refreshes
+    }
+
+    fn commands(log: &Log) -> Vec<u8> {
+        log.borrow_mut()
+            .drain(..)
+            .map(|(command, _)| command)
+            .collect()
+    }
+
+    #[rstest]
+    fn sends_frame_buffer() {
+        let (mut epd, log) = black_white_epd(RefreshPolicy::new());
+        epd.fill_solid(
+            &Rectangle::new(embedded_graphics::geometry::Point::zero(), Size::new(8, 1)),
+            Color::White,
+        )
+        .unwrap();
+        epd.full_refresh().unwrap();
+
+        let log = log.borrow();
+        let (_, buffer) = log
+            .iter()
+            .find(|(command, _)| *command == WRITE_BW_DATA)
+            .unwrap();
+        assert_eq!(buffer.as_slice(), epd.display().buffer());
+    }
+
+    #[rstest]
+    fn refreshes_black_white_fully_and_partially() {
+        let (mut epd, log) = black_white_epd(RefreshPolicy::new());
+
+        epd.full_refresh().unwrap();
+        assert_eq!(refreshes(&log), [DISPLAY_MODE_1]);
+
+        epd.partial_refresh().unwrap();
+        let partial = log
+            .borrow()
+            .iter()
+            .any(|(command, _)| *command == WRITE_LUT);
+        assert!(partial);
+        let modes = refreshes(&log);
+        assert_eq!(modes.len(), 1);
+        assert_ne!(modes, [DISPLAY_MODE_1]);
+    }
+
+    #[rstest]
+    fn refreshes_tricolor_fully() {
+        let log = Log::default();
+        let mut driver = WeActStudio290TriColorDriver::new(
+            MockInterface(log.clone()),
+            MockPin,
+            MockPin,
+            MockDelay,
+        );
+        driver.init().unwrap();
+        log.borrow_mut().clear();
+        let mut epd = WeActEpd::new(driver, Display290TriColor::new(), RefreshPolicy::new());
+
+        epd.partial_refresh().unwrap();
+        assert_eq!(refreshes(&log), [DISPLAY_MODE_1]);
+    }
+
+    #[rstest]
+    fn sleeps_and_wakes_up() {
+        let (mut epd, log) = black_white_epd(RefreshPolicy::new());
+        assert_eq!(epd.flush(Duration::ZERO).unwrap(), Refresh::Full);
+        assert_eq!(epd.flush(Duration::from_secs(1)).unwrap(), Refresh::Partial);
+        log.borrow_mut().clear();
+
+        epd.sleep().unwrap();
+        assert_eq!(commands(&log), [DEEP_SLEEP]);
+
+        epd.wake_up().unwrap();
+        assert_eq!(epd.flush(Duration::from_secs(2)).unwrap(), Refresh::Full);
+        assert_eq!(refreshes(&log), [DISPLAY_MODE_1]);
+        assert_eq!(epd.flush(Duration::from_secs(3)).unwrap(), Refresh::Partial);
+    }
+
+    #[rstest]
+    fn flushes_through_backend() {
+        use crate::{EmbeddedBackend, EmbeddedBackendConfig};
+        use ratatui::Terminal;
+
+        let (mut epd, log) = black_white_epd(RefreshPolicy::new());
+        let config = EmbeddedBackendConfig {
+            flush_callback: crate::epd::epd_flush_callback(|| Duration::ZERO),
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(EmbeddedBackend::new(&mut epd, config)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget("mousefood", frame.area()))
+            .unwrap();
+
+        assert_eq!(refreshes(&log), [DISPLAY_MODE_1]);
+    }
+
+    #[rstest]
+    fn returns_driver_error_from_backend() {
+        use crate::error::{DrawTargetError, EpdRefreshError, Error};
+        use crate::{EmbeddedBackend, EmbeddedBackendConfig};
+        use ratatui::Terminal;
+
+        /// Display interface of a disconnected panel.
+        struct FailingInterface;
+
+        impl WriteOnlyDataCommand for FailingInterface {
+            fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+                Err(DisplayError::BusWriteError)
+            }
+
+            fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+                Err(DisplayError::BusWriteError)
+            }
+        }
+
+        let driver =
+            WeActStudio290BlackWhiteDriver::new(FailingInterface, MockPin, MockPin, MockDelay);
+        let mut epd = WeActEpd::new(driver, Display290BlackWhite::new(), RefreshPolicy::new());
+        let config = EmbeddedBackendConfig {
+            flush_callback: crate::epd::epd_flush_callback(|| Duration::ZERO),
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(EmbeddedBackend::new(&mut epd, config)).unwrap();
+
+        let error = terminal.draw(|_| {}).unwrap_err();
+        let Error::FlushError(source) = error else {
+            panic!("expected a flush error, got {error:?}");
+        };
+        let Some(EpdRefreshError(DrawTargetError(driver_error))) =
+            source.downcast_ref::<EpdRefreshError<DisplayError>>()
+        else {
+            panic!("expected an EPD refresh error, got {source:?}");
+        };
+        assert!(matches!(driver_error, DisplayError::BusWriteError));
+        assert_eq!(
+            source.source().map(alloc::string::ToString::to_string),
+            Some("BusWriteError".into())
+        );
+    }
+}
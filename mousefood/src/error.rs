@@ -1,13 +1,90 @@
-//! Mousefood `Error` enum.
+//! Mousefood error types.
+
+use core::convert::Infallible;
+use core::fmt;
 
 /// Represents backend error.
+///
+/// `E` is the error of the [`DrawTarget`](embedded_graphics::draw_target::DrawTarget)
+/// of the backend, e.g. of the display driver.
 #[derive(thiserror::Error, Debug)]
-pub enum Error {
+pub enum Error<E = Infallible> {
     /// Drawing to the display failed.
     #[error("drawing to DrawTarget failed")]
-    DrawError,
+    DrawError(#[source] DrawTargetError<E>),
+
+    /// The [flush callback](crate::EmbeddedBackendConfig::flush_callback) failed.
+    #[error("flushing the display failed")]
+    FlushError(#[source] FlushError),
+
+    /// The [framebuffer memory](crate::EmbeddedBackendConfig::framebuffer_memory) is too small,
+    /// e.g. for the bands of larger fonts.
+    #[error("framebuffer memory holds {available} elements, {needed} are needed")]
+    FramebufferMemoryTooSmall {
+        /// Number of elements needed.
+        needed: usize,
+        /// Number of elements of the framebuffer memory.
+        available: usize,
+    },
 
     /// Selected [`ClearType`](ratatui_core::backend::ClearType) is not supported by Mousefood.
     #[error("ClearType::{0} is not supported by Mousefood")]
     ClearTypeUnsupported(alloc::string::String),
 }
+
+/// Error of a [`DrawTarget`](embedded_graphics::draw_target::DrawTarget).
+///
+/// Display drivers' errors often implement only [`Debug`](fmt::Debug),
+/// so this wrapper displays the error with its `Debug` representation,
+/// e.g. as the [source](core::error::Error::source) of [`Error::DrawError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawTargetError<E>(pub E);
+
+impl<E: fmt::Debug> fmt::Display for DrawTargetError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for DrawTargetError<E> {}
+
+/// Error of the driver of an [`EpdDisplay`](crate::EpdDisplay), returned by the flush callback
+/// of [`epd_flush_callback`](crate::epd_flush_callback).
+///
+/// The driver error is kept as the [source](core::error::Error::source), so it can be
+/// retrieved by downcasting the [`FlushError`] of [`Error::FlushError`].
+#[cfg(any(feature = "epd-weact", feature = "epd-waveshare"))]
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("refreshing the e-paper display failed")]
+pub struct EpdRefreshError<E: fmt::Debug>(#[source] pub DrawTargetError<E>);
+
+/// Error returned by a [flush callback](crate::EmbeddedBackendConfig::flush_callback),
+/// e.g. when sending the frame to the display over SPI failed.
+///
+/// Messages convert into it with `?` or `.into()`.
+pub type FlushError = alloc::boxed::Box<dyn core::error::Error + Send + Sync>;
+
+/// Represents an error while parsing a color scheme into a [`ColorTheme`](crate::ColorTheme).
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeParseError {
+    /// A color value could not be parsed.
+    #[error("invalid color on line {line}")]
+    InvalidColor {
+        /// Line of the invalid color, starting at 1.
+        line: usize,
+    },
+
+    /// The color scheme doesn't define any known color.
+    #[error("no colors found in the color scheme")]
+    NoColors,
+}
+
+impl ThemeParseError {
+    /// Returns a description of the error that can be used in `const` contexts.
+    pub const fn message(&self) -> &'static str {
+        match self {
+            Self::InvalidColor { .. } => "invalid color in the color scheme",
+            Self::NoColors => "no colors found in the color scheme",
+        }
+    }
+}
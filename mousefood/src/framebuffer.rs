@@ -6,16 +6,164 @@
 //! with display drivers that don't provide their own internal framebuffer. This helps
 //! prevent screen tearing by allowing you to compose a complete frame in memory before
 //! sending it to the display.
+//!
+//! The pixels are stored in one of the [`FramebufferLayout`]s, as colors, packed into bytes or
+//! as palette indices.
+//! They can also be stored in static memory provided by the application,
+//! e.g. placed in a PSRAM or DMA-capable linker section.
 
-use alloc::{vec, vec::IntoIter, vec::Vec};
+use alloc::vec;
 
-use crate::colors::{ColorTheme, TermColor, TermColorType};
 use embedded_graphics::Pixel;
 use embedded_graphics::draw_target::DrawTarget;
-use embedded_graphics::geometry::Dimensions;
-use embedded_graphics::pixelcolor::PixelColor;
+use embedded_graphics::geometry::{Dimensions, Point, Size};
+use embedded_graphics::pixelcolor::raw::RawData;
+use embedded_graphics::pixelcolor::{PixelColor, Rgb888};
 use embedded_graphics::primitives::Rectangle;
-use ratatui_core::style::Color;
+
+use crate::indexed_buffer::{IndexBits, Palette};
+use crate::memory::Memory;
+use crate::packed_buffer::Packing;
+
+/// How the framebuffer of the backend stores its pixels,
+/// see [`EmbeddedBackendConfig::framebuffer_layout`](crate::EmbeddedBackendConfig::framebuffer_layout).
+#[derive(Clone, Copy)]
+pub struct FramebufferLayout<C: 'static>(Layout<C>);
+
+#[derive(Clone, Copy)]
+enum Layout<C: 'static> {
+    Native,
+    Packed(Packing<C>),
+    Indexed(IndexBits, fn(C, C) -> u32),
+}
+
+impl<C: PixelColor> FramebufferLayout<C> {
+    /// Stores a `C` for every pixel.
+    pub const fn native() -> Self {
+        Self(Layout::Native)
+    }
+
+    /// Stores the raw data of the pixel colors, packed into bytes which can be sent to the
+    /// display as they are, e.g. over SPI: 8 pixels per byte for `BinaryColor`, 2 or 4 bits per
+    /// pixel for `Gray2` and `Gray4`, big-endian `u16` for `Rgb565`.
+    ///
+    /// Pixels are stored row by row. Colors of less than 8 bits are packed most significant bits
+    /// first, and every row starts on a new byte. Colors of 8 bits or more are stored big-endian.
+    /// The raw data is the one of embedded-graphics, e.g. `BinaryColor::On` is stored as `1`.
+    ///
+    /// The bytes are returned by
+    /// [`EmbeddedBackend::front_buffer_bytes`](crate::EmbeddedBackend::front_buffer_bytes).
+    pub fn packed() -> Self
+    where
+        C: From<C::Raw>,
+        C::Raw: From<C>,
+        <C::Raw as RawData>::Storage: Into<u32>,
+    {
+        Self(Layout::Packed(Packing::new()))
+    }
+
+    /// Stores a 4-bit or 8-bit index into a palette for every pixel, and expands the indices to
+    /// colors when the framebuffer is sent to the display. A 320 × 240 framebuffer takes 38 KB
+    /// with 4-bit indices, instead of 150 KB with `Rgb565` colors.
+    ///
+    /// The palette is filled with the colors as they are drawn. Entries are reserved for the
+    /// display colors of `Color::Reset` and of the 16 named colors, as the theme and the filter
+    /// map them, so that they are always drawn exactly. The other colors, e.g. `Color::Rgb`,
+    /// share the remaining entries, which are reused once no pixel shows their color anymore.
+    ///
+    /// # Palette overflow
+    ///
+    /// When a frame shows more colors than the palette holds, the additional colors are drawn
+    /// with the closest color of the palette, and
+    /// [`EmbeddedBackend::palette_overflows`](crate::EmbeddedBackend::palette_overflows) counts
+    /// the pixels drawn that way. With [`IndexBits::Four`], the theme colors leave few or no
+    /// entries, so `Color::Rgb` content needs [`IndexBits::Eight`].
+    pub fn indexed(bits: IndexBits) -> Self
+    where
+        C: Into<Rgb888>,
+    {
+        Self(Layout::Indexed(bits, |a, b| {
+            crate::ink_palette::distance(a.into(), b.into())
+        }))
+    }
+
+    /// Returns the number of elements of [`FramebufferMemory`] needed for `size` pixels:
+    /// pixels with the native layout, bytes with the others.
+    pub fn memory_len(&self, size: Size) -> usize {
+        match &self.0 {
+            Layout::Native => HeapBuffer::<C>::pixel_count(Rectangle::new(Point::zero(), size)),
+            Layout::Packed(packing) => packing.byte_count(size),
+            Layout::Indexed(bits, _) => bits.byte_count(size),
+        }
+    }
+
+    /// Checks that `memory` holds the elements of this layout.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `memory` doesn't match the layout.
+    pub(crate) fn assert_memory(&self, memory: &FramebufferMemory<C>) {
+        match (&self.0, memory) {
+            (Layout::Native, FramebufferMemory::Bytes(_)) => {
+                panic!("the native framebuffer layout needs FramebufferMemory::Pixels")
+            }
+            (Layout::Packed(_), FramebufferMemory::Pixels(_)) => {
+                panic!("the packed framebuffer layout needs FramebufferMemory::Bytes")
+            }
+            (Layout::Indexed(..), FramebufferMemory::Pixels(_)) => {
+                panic!("the indexed framebuffer layout needs FramebufferMemory::Bytes")
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<C: PixelColor> Default for FramebufferLayout<C> {
+    fn default() -> Self {
+        Self::native()
+    }
+}
+
+/// Static memory of the framebuffer,
+/// see [`EmbeddedBackendConfig::framebuffer_memory`](crate::EmbeddedBackendConfig::framebuffer_memory).
+pub enum FramebufferMemory<C: 'static> {
+    /// Memory of the [native](FramebufferLayout::native) layout, a color for every pixel.
+    Pixels(&'static mut [C]),
+    /// Memory of the [packed](FramebufferLayout::packed) and
+    /// [indexed](FramebufferLayout::indexed) layouts.
+    Bytes(&'static mut [u8]),
+}
+
+impl<C> FramebufferMemory<C> {
+    /// Returns the number of elements of the memory.
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::Pixels(memory) => memory.len(),
+            Self::Bytes(memory) => memory.len(),
+        }
+    }
+
+    /// Splits the memory into its first `mid` elements and the others.
+    pub(crate) fn split_at(self, mid: usize) -> (Self, Self) {
+        match self {
+            Self::Pixels(memory) => {
+                let (first, second) = memory.split_at_mut(mid);
+                (Self::Pixels(first), Self::Pixels(second))
+            }
+            Self::Bytes(memory) => {
+                let (first, second) = memory.split_at_mut(mid);
+                (Self::Bytes(first), Self::Bytes(second))
+            }
+        }
+    }
+}
+
+/// Pixels of a [`HeapBuffer`] in one of the [`FramebufferLayout`]s.
+enum Storage<C: 'static> {
+    Native(Memory<C>),
+    Packed(Memory<u8>, Packing<C>),
+    Indexed(Memory<u8>, Palette<C>),
+}
 
 /// A heap-allocated framebuffer for storing pixels before rendering to a display.
 ///
@@ -24,43 +172,122 @@ use ratatui_core::style::Color;
 /// allowing you to compose a complete frame in memory before sending it to the display.
 ///
 /// The framebuffer is generic over any pixel color type that implements [`PixelColor`]
-/// and [`Copy`]. It stores pixels in a contiguous `Vec<C>` arranged in row-major order.
+/// and [`Copy`]. It stores pixels in row-major order, in a [`FramebufferLayout`].
 ///
 /// # Type Parameters
 ///
 /// * `C` - The pixel color type. Must implement [`PixelColor`] and [`Copy`].
-pub struct HeapBuffer<C: PixelColor + Copy> {
-    data: Vec<C>,
+pub struct HeapBuffer<C: PixelColor + Copy + 'static> {
+    data: Storage<C>,
     bounding_box: Rectangle,
 }
 
-impl<C: PixelColor + for<'a> From<TermColor<'a>>> HeapBuffer<C> {
+impl<C: PixelColor> HeapBuffer<C> {
     /// Creates a new framebuffer with the specified dimensions.
     ///
-    /// The framebuffer is initialized with a background color derived from [`Color::Reset`].
+    /// The framebuffer is initialized with the `background` color.
     ///
     /// # Arguments
     ///
     /// * `bounding_box` - A [`Rectangle`] defining the position and size of the framebuffer.
     ///   The framebuffer will have `width × height` pixels, where width and height
     ///   are taken from the rectangle's size.
+    /// * `background` - The initial color of all pixels.
     ///
     /// # Returns
     ///
     /// A new [`HeapBuffer`] instance with the specified dimensions, initialized with
-    /// the default background color.
+    /// the background color.
     ///
     /// # Memory Usage
     ///
     /// This method allocates `width × height × sizeof(C)` bytes of memory on the heap,
     /// where `C` is the pixel color type.
-    pub fn new(bounding_box: Rectangle, color_theme: ColorTheme) -> HeapBuffer<C> {
-        Self {
-            data: vec![
-                TermColor::new(Color::Reset, TermColorType::Background, &color_theme).into();
-                (bounding_box.size.width * bounding_box.size.height) as usize
-            ],
-            bounding_box,
+    pub fn new(bounding_box: Rectangle, background: C) -> HeapBuffer<C> {
+        Self::with_layout(bounding_box, background, FramebufferLayout::native(), None)
+    }
+
+    /// Creates a new framebuffer storing its pixels in `layout`, in `memory` instead of the heap
+    /// if it is provided.
+    ///
+    /// The framebuffer is initialized with the `background` color.
+    /// Only the first [`memory_len`](FramebufferLayout::memory_len) elements of `memory` are
+    /// used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `memory` is too small, or doesn't match `layout`.
+    pub fn with_layout(
+        bounding_box: Rectangle,
+        background: C,
+        layout: FramebufferLayout<C>,
+        memory: Option<FramebufferMemory<C>>,
+    ) -> HeapBuffer<C> {
+        let len = layout.memory_len(bounding_box.size);
+        if let Some(memory) = &memory {
+            layout.assert_memory(memory);
+        }
+        let data = match (layout.0, memory) {
+            (Layout::Native, None) => Storage::Native(Memory::Heap(vec![background; len])),
+            (Layout::Native, Some(FramebufferMemory::Pixels(memory))) => {
+                Storage::Native(Memory::from_static(memory, len, background))
+            }
+            (Layout::Packed(packing), None) => Storage::Packed(Memory::Heap(vec![0; len]), packing),
+            (Layout::Packed(packing), Some(FramebufferMemory::Bytes(memory))) => {
+                Storage::Packed(Memory::from_static(memory, len, 0), packing)
+            }
+            (Layout::Indexed(bits, distance), None) => {
+                Storage::Indexed(Memory::Heap(vec![0; len]), Palette::new(bits, distance))
+            }
+            (Layout::Indexed(bits, distance), Some(FramebufferMemory::Bytes(memory))) => {
+                Storage::Indexed(
+                    Memory::from_static(memory, len, 0),
+                    Palette::new(bits, distance),
+                )
+            }
+            _ => unreachable!("the memory matches the layout"),
+        };
+        let mut buffer = Self { data, bounding_box };
+        buffer.fill(background);
+        buffer
+    }
+
+    /// Returns the number of pixels of a framebuffer covering `bounding_box`.
+    pub fn pixel_count(bounding_box: Rectangle) -> usize {
+        (bounding_box.size.width * bounding_box.size.height) as usize
+    }
+
+    /// Returns the number of elements of memory needed for `size` pixels.
+    pub fn memory_len(&self, size: Size) -> usize {
+        match &self.data {
+            Storage::Native(_) => Self::pixel_count(Rectangle::new(Point::zero(), size)),
+            Storage::Packed(_, packing) => packing.byte_count(size),
+            Storage::Indexed(_, palette) => palette.byte_count(size),
+        }
+    }
+
+    /// Returns the number of elements of the memory of the framebuffer.
+    fn capacity(&self) -> usize {
+        match &self.data {
+            Storage::Native(data) => data.len(),
+            Storage::Packed(data, _) | Storage::Indexed(data, _) => data.len(),
+        }
+    }
+
+    /// Reserves palette entries for `colors` with the [indexed](FramebufferLayout::indexed)
+    /// layout, see [`Palette`]. Does nothing with the other layouts.
+    pub(crate) fn reserve_colors(&mut self, colors: impl IntoIterator<Item = C>) {
+        if let Storage::Indexed(_, palette) = &mut self.data {
+            palette.reserve(colors);
+        }
+    }
+
+    /// Returns the number of pixels drawn with the closest color of the palette, as it was
+    /// full, with the [indexed](FramebufferLayout::indexed) layout.
+    pub(crate) fn palette_overflows(&self) -> usize {
+        match &self.data {
+            Storage::Indexed(_, palette) => palette.overflows(),
+            _ => 0,
         }
     }
 }
@@ -70,16 +297,54 @@ impl<C: PixelColor> IntoIterator for HeapBuffer<C> {
     type IntoIter = IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.data.into_iter()
+        IntoIter {
+            buffer: self,
+            index: 0,
+        }
+    }
+}
+
+/// Iterator over the pixels of a [`HeapBuffer`], in row-major order.
+pub struct IntoIter<C: PixelColor + 'static> {
+    buffer: HeapBuffer<C>,
+    index: usize,
+}
+
+impl<C: PixelColor> Iterator for IntoIter<C> {
+    type Item = C;
+
+    fn next(&mut self) -> Option<C> {
+        let pixel = self.buffer.color(self.index);
+        self.index += 1;
+        pixel
+    }
+}
+
+/// Iterator over the pixels of a borrowed [`HeapBuffer`], in row-major order.
+pub struct Colors<'a, C: PixelColor + 'static> {
+    buffer: &'a HeapBuffer<C>,
+    index: usize,
+}
+
+impl<C: PixelColor> Iterator for Colors<'_, C> {
+    type Item = C;
+
+    fn next(&mut self) -> Option<C> {
+        let pixel = self.buffer.color(self.index);
+        self.index += 1;
+        pixel
     }
 }
 
 impl<'a, C: PixelColor + Copy> IntoIterator for &'a HeapBuffer<C> {
     type Item = C;
-    type IntoIter = core::iter::Copied<core::slice::Iter<'a, C>>;
+    type IntoIter = Colors<'a, C>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.data.iter().copied()
+        Colors {
+            buffer: self,
+            index: 0,
+        }
     }
 }
 
@@ -89,6 +354,126 @@ impl<C: PixelColor> Dimensions for HeapBuffer<C> {
     }
 }
 
+impl<C: PixelColor> HeapBuffer<C> {
+    /// Returns the column and the row of the pixel at `point`, or `None` if it lies outside of
+    /// the framebuffer.
+    fn position(&self, point: Point) -> Option<(usize, usize)> {
+        let Point { x, y } = point - self.bounding_box.top_left;
+        let Size { width, height } = self.bounding_box.size;
+        if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    /// Returns the color of the pixel at column `x` of row `y`.
+    fn get(&self, x: usize, y: usize) -> C {
+        let width = self.bounding_box.size.width;
+        match &self.data {
+            Storage::Native(data) => data[y * width as usize + x],
+            Storage::Packed(data, packing) => packing.get(data, packing.stride(width), x, y),
+            Storage::Indexed(data, palette) => palette.get(data, y * width as usize + x),
+        }
+    }
+
+    /// Sets the pixel at column `x` of row `y`.
+    fn set(&mut self, x: usize, y: usize, color: C) {
+        let width = self.bounding_box.size.width;
+        match &mut self.data {
+            Storage::Native(data) => data[y * width as usize + x] = color,
+            Storage::Packed(data, packing) => {
+                packing.set(data, packing.stride(width), x, y, color);
+            }
+            Storage::Indexed(data, palette) => palette.set(data, y * width as usize + x, color),
+        }
+    }
+
+    /// Returns the color of the pixel at position `index` in row-major order,
+    /// or `None` past the last pixel.
+    fn color(&self, index: usize) -> Option<C> {
+        let width = self.bounding_box.size.width as usize;
+        (index < Self::pixel_count(self.bounding_box))
+            .then(|| self.get(index % width, index / width))
+    }
+
+    /// Returns the pixels of the framebuffer in row-major order,
+    /// or `None` if they aren't stored in the [native](FramebufferLayout::native) layout.
+    pub fn pixels(&self) -> Option<&[C]> {
+        match &self.data {
+            Storage::Native(data) => Some(&data[..Self::pixel_count(self.bounding_box)]),
+            Storage::Packed(..) | Storage::Indexed(..) => None,
+        }
+    }
+
+    /// Returns the bytes of the framebuffer,
+    /// or `None` if it doesn't store its pixels in the [packed](FramebufferLayout::packed) layout.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.data {
+            Storage::Native(_) | Storage::Indexed(..) => None,
+            Storage::Packed(data, packing) => {
+                Some(&data[..packing.byte_count(self.bounding_box.size)])
+            }
+        }
+    }
+
+    /// Moves the framebuffer to `bounding_box`, e.g. to render the next band of the display.
+    ///
+    /// The pixels are left as they are, row-major in the new bounding box.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bounding_box` has more pixels than the framebuffer holds.
+    pub(crate) fn set_bounding_box(&mut self, bounding_box: Rectangle) {
+        assert!(self.memory_len(bounding_box.size) <= self.capacity());
+        self.bounding_box = bounding_box;
+    }
+
+    /// Moves the framebuffer to `bounding_box`, growing memory on the heap with `background`
+    /// if it holds too few pixels.
+    ///
+    /// Returns the number of elements of static memory which is too small as the error.
+    pub(crate) fn resize(&mut self, bounding_box: Rectangle, background: C) -> Result<(), usize> {
+        let len = self.memory_len(bounding_box.size);
+        match &mut self.data {
+            Storage::Native(data) => data.reserve(len, background)?,
+            Storage::Packed(data, _) | Storage::Indexed(data, _) => data.reserve(len, 0)?,
+        }
+        self.bounding_box = bounding_box;
+        Ok(())
+    }
+
+    /// Copies the pixels of `area` from `source`, a framebuffer with the same bounding box.
+    pub fn copy_area_from(&mut self, source: &HeapBuffer<C>, area: &Rectangle) {
+        debug_assert_eq!(self.bounding_box, source.bounding_box);
+        let area = area.intersection(&self.bounding_box);
+        let width = area.size.width as usize;
+        for y in area.rows() {
+            let Some((x, y)) = self.position(Point::new(area.top_left.x, y)) else {
+                continue;
+            };
+            if let (Storage::Native(data), Storage::Native(source)) = (&mut self.data, &source.data)
+            {
+                let start = y * self.bounding_box.size.width as usize + x;
+                data[start..start + width].copy_from_slice(&source[start..start + width]);
+                continue;
+            }
+            for x in x..x + width {
+                self.set(x, y, source.get(x, y));
+            }
+        }
+    }
+
+    /// Sets all pixels to `color`.
+    fn fill(&mut self, color: C) {
+        let len = self.memory_len(self.bounding_box.size);
+        match &mut self.data {
+            Storage::Native(data) => data[..len].fill(color),
+            Storage::Packed(data, packing) => packing.fill(&mut data[..len], color),
+            Storage::Indexed(data, palette) => palette.fill(&mut data[..len], color),
+        }
+    }
+}
+
 impl<C: PixelColor> DrawTarget for HeapBuffer<C> {
     type Color = C;
     type Error = core::convert::Infallible;
@@ -97,13 +482,18 @@ impl<C: PixelColor> DrawTarget for HeapBuffer<C> {
     where
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        let idx_end = self.data.len() - 1;
         for Pixel(point, color) in pixels {
-            let idx = point.y as usize * self.bounding_box.size.width as usize + point.x as usize;
-            self.data[idx.clamp(0, idx_end)] = color;
+            if let Some((x, y)) = self.position(point) {
+                self.set(x, y, color);
+            }
         }
         Ok(())
     }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill(color);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -114,12 +504,12 @@ mod tests {
     use embedded_graphics::mock_display::MockDisplay;
     use embedded_graphics::mono_font::MonoTextStyle;
     use embedded_graphics::mono_font::ascii::FONT_4X6;
-    use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+    use embedded_graphics::pixelcolor::{BinaryColor, Rgb888, RgbColor};
     use embedded_graphics::prelude::*;
     use embedded_graphics::text::Text;
 
     #[fixture]
-    fn display() -> MockDisplay<Rgb888> {
+    fn display<C: PixelColor>() -> MockDisplay<C> {
         MockDisplay::new()
     }
 
@@ -127,7 +517,7 @@ mod tests {
     fn heap_buffer() -> HeapBuffer<Rgb888> {
         HeapBuffer::new(
             Rectangle::new(Point::zero(), Size::new(16, 8)),
-            ColorTheme::default(),
+            Rgb888::BLACK,
         )
     }
 
@@ -181,4 +571,85 @@ mod tests {
 
         display.assert_pattern(expected);
     }
+
+    #[rstest]
+    fn test_heap_buffer_with_offset(mut display: MockDisplay<Rgb888>) {
+        let bounding_box = Rectangle::new(Point::new(2, 1), Size::new(3, 2));
+        let mut heap_buffer = HeapBuffer::new(bounding_box, Rgb888::BLACK);
+
+        heap_buffer
+            .draw_iter([
+                Pixel(Point::new(2, 1), Rgb888::WHITE),
+                Pixel(Point::new(4, 2), Rgb888::RED),
+                // outside of the framebuffer, must be clipped
+                Pixel(Point::new(0, 0), Rgb888::GREEN),
+                Pixel(Point::new(5, 1), Rgb888::GREEN),
+                Pixel(Point::new(2, 3), Rgb888::GREEN),
+            ])
+            .unwrap();
+
+        display
+            .fill_contiguous(&heap_buffer.bounding_box(), &heap_buffer)
+            .unwrap();
+
+        display.assert_pattern(&["     ", "  WKK", "  KKR"]);
+    }
+
+    #[rstest]
+    fn test_heap_buffer_copy_area(mut display: MockDisplay<Rgb888>) {
+        let bounding_box = Rectangle::new(Point::new(1, 1), Size::new(4, 3));
+        let mut source = HeapBuffer::new(bounding_box, Rgb888::WHITE);
+        source
+            .draw_iter([Pixel(Point::new(1, 1), Rgb888::RED)])
+            .unwrap();
+        let mut heap_buffer = HeapBuffer::new(bounding_box, Rgb888::BLACK);
+
+        // partly outside of the framebuffer, must be clipped
+        heap_buffer.copy_area_from(&source, &Rectangle::new(Point::new(0, 0), Size::new(3, 3)));
+
+        display
+            .fill_contiguous(&heap_buffer.bounding_box(), &heap_buffer)
+            .unwrap();
+
+        display.assert_pattern(&["     ", " RWKK", " WWKK", " KKKK"]);
+    }
+
+    #[rstest]
+    fn test_packed_heap_buffer(mut display: MockDisplay<BinaryColor>) {
+        let bounding_box = Rectangle::new(Point::new(1, 1), Size::new(10, 2));
+        let mut source = HeapBuffer::with_layout(
+            bounding_box,
+            BinaryColor::Off,
+            FramebufferLayout::packed(),
+            None,
+        );
+        source
+            .draw_iter([
+                Pixel(Point::new(1, 1), BinaryColor::On),
+                Pixel(Point::new(10, 1), BinaryColor::On),
+                Pixel(Point::new(2, 2), BinaryColor::On),
+                // outside of the framebuffer, must be clipped
+                Pixel(Point::new(11, 1), BinaryColor::On),
+            ])
+            .unwrap();
+        assert_eq!(source.pixels(), None);
+        assert_eq!(
+            source.as_bytes(),
+            Some([0b1000_0000, 0b0100_0000, 0b0100_0000, 0b0000_0000].as_slice())
+        );
+
+        let mut heap_buffer = HeapBuffer::with_layout(
+            bounding_box,
+            BinaryColor::Off,
+            FramebufferLayout::packed(),
+            None,
+        );
+        heap_buffer.copy_area_from(&source, &Rectangle::new(Point::new(1, 1), Size::new(2, 2)));
+
+        display
+            .fill_contiguous(&heap_buffer.bounding_box(), &heap_buffer)
+            .unwrap();
+
+        display.assert_pattern(&["           ", " #.........", " .#........"]);
+    }
 }
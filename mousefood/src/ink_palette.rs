@@ -0,0 +1,323 @@
+//! # Nearest color mapping for displays with a few colors
+//!
+//! E-paper displays can only show a handful of inks (e.g. black, white and red).
+//! [`InkPalette`] describes how each ink looks and maps any color to the perceptually
+//! nearest ink.
+
+use embedded_graphics::pixelcolor::{PixelColor, Rgb888, RgbColor};
+
+/// Display colors (inks) of a target, together with their appearance.
+///
+/// # Examples
+///
+/// A three-color e-paper display with orange-ish red ink:
+///
+/// ```rust
+/// use mousefood::InkPalette;
+/// use mousefood::embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+///
+/// #[derive(Clone, Copy, PartialEq, Debug)]
+/// enum Ink {
+///     Black,
+///     White,
+///     Red,
+/// }
+/// # impl mousefood::embedded_graphics::pixelcolor::PixelColor for Ink {
+/// #     type Raw = ();
+/// # }
+///
+/// const INKS: InkPalette<Ink> = InkPalette::new(&[
+///     (Ink::Black, Rgb888::BLACK),
+///     (Ink::White, Rgb888::WHITE),
+///     (Ink::Red, Rgb888::new(230, 80, 40)),
+/// ]);
+///
+/// assert_eq!(INKS.nearest(Rgb888::new(255, 127, 127)), Ink::Red);
+/// assert_eq!(INKS.nearest(Rgb888::new(200, 200, 200)), Ink::White);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct InkPalette<C: 'static> {
+    inks: &'static [(C, Rgb888)],
+}
+
+impl<C: PixelColor> InkPalette<C> {
+    /// Creates a palette from display colors and their appearance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `inks` is empty.
+    pub const fn new(inks: &'static [(C, Rgb888)]) -> Self {
+        assert!(!inks.is_empty(), "ink palette must not be empty");
+        Self { inks }
+    }
+
+    /// Returns the display colors and their appearance.
+    pub const fn inks(&self) -> &'static [(C, Rgb888)] {
+        self.inks
+    }
+
+    /// Returns the ink perceptually nearest to `color`.
+    pub fn nearest(&self, color: Rgb888) -> C {
+        let mut nearest = self.inks[0];
+        for &ink in &self.inks[1..] {
+            if distance(ink.1, color) < distance(nearest.1, color) {
+                nearest = ink;
+            }
+        }
+        nearest.0
+    }
+
+    /// Returns the ink nearest to `color` offset by the dithering `threshold`.
+    ///
+    /// Colors matching an ink exactly are never dithered.
+    pub(crate) fn dithered(&self, color: Rgb888, threshold: u8) -> C {
+        if let Some(&(ink, _)) = self.inks.iter().find(|(_, rgb)| *rgb == color) {
+            return ink;
+        }
+        let offset = |channel: u8| (channel as i16 + 128 - threshold as i16).clamp(0, 255) as u8;
+        self.nearest(Rgb888::new(
+            offset(color.r()),
+            offset(color.g()),
+            offset(color.b()),
+        ))
+    }
+}
+
+/// Squared "redmean" distance between two colors.
+///
+/// A cheap approximation of perceptual color difference, which weights the channels
+/// depending on the amount of red.
+pub(crate) fn distance(a: Rgb888, b: Rgb888) -> u32 {
+    let mean_r = (a.r() as i32 + b.r() as i32) / 2;
+    let dr = a.r() as i32 - b.r() as i32;
+    let dg = a.g() as i32 - b.g() as i32;
+    let db = a.b() as i32 - b.b() as i32;
+    ((((512 + mean_r) * dr * dr) >> 8) + 4 * dg * dg + (((767 - mean_r) * db * db) >> 8)) as u32
+}
+
+impl InkPalette<embedded_graphics::pixelcolor::BinaryColor> {
+    /// Black (`Off`) and white (`On`).
+    pub const DEFAULT: Self = Self::new(&[
+        (
+            embedded_graphics::pixelcolor::BinaryColor::Off,
+            Rgb888::BLACK,
+        ),
+        (
+            embedded_graphics::pixelcolor::BinaryColor::On,
+            Rgb888::WHITE,
+        ),
+    ]);
+}
+
+#[cfg(feature = "epd-weact")]
+impl InkPalette<weact_studio_epd::Color> {
+    /// Black and white inks.
+    pub const DEFAULT: Self = Self::new(&[
+        (weact_studio_epd::Color::Black, Rgb888::BLACK),
+        (weact_studio_epd::Color::White, Rgb888::WHITE),
+    ]);
+}
+
+#[cfg(feature = "epd-weact")]
+impl InkPalette<weact_studio_epd::TriColor> {
+    /// Black, white and red inks.
+    pub const DEFAULT: Self = Self::new(&[
+        (weact_studio_epd::TriColor::Black, Rgb888::BLACK),
+        (weact_studio_epd::TriColor::White, Rgb888::WHITE),
+        (weact_studio_epd::TriColor::Red, Rgb888::RED),
+    ]);
+}
+
+#[cfg(feature = "epd-waveshare")]
+impl InkPalette<epd_waveshare::color::Color> {
+    /// Black and white inks.
+    pub const DEFAULT: Self = Self::new(&[
+        (epd_waveshare::color::Color::Black, Rgb888::BLACK),
+        (epd_waveshare::color::Color::White, Rgb888::WHITE),
+    ]);
+}
+
+#[cfg(feature = "epd-waveshare")]
+impl InkPalette<epd_waveshare::color::TriColor> {
+    /// Black, white and red (chromatic) inks.
+    pub const DEFAULT: Self = Self::new(&[
+        (epd_waveshare::color::TriColor::Black, Rgb888::BLACK),
+        (epd_waveshare::color::TriColor::White, Rgb888::WHITE),
+        (epd_waveshare::color::TriColor::Chromatic, Rgb888::RED),
+    ]);
+
+    /// Black, white and yellow (chromatic) inks, for displays with yellow ink.
+    pub const YELLOW: Self = Self::new(&[
+        (epd_waveshare::color::TriColor::Black, Rgb888::BLACK),
+        (epd_waveshare::color::TriColor::White, Rgb888::WHITE),
+        (epd_waveshare::color::TriColor::Chromatic, Rgb888::YELLOW),
+    ]);
+}
+
+#[cfg(feature = "epd-waveshare")]
+impl InkPalette<epd_waveshare::color::OctColor> {
+    /// The seven inks of ACeP displays.
+    pub const DEFAULT: Self = Self::new(&[
+        (epd_waveshare::color::OctColor::Black, Rgb888::BLACK),
+        (epd_waveshare::color::OctColor::White, Rgb888::WHITE),
+        (epd_waveshare::color::OctColor::Green, Rgb888::GREEN),
+        (epd_waveshare::color::OctColor::Blue, Rgb888::BLUE),
+        (epd_waveshare::color::OctColor::Red, Rgb888::RED),
+        (epd_waveshare::color::OctColor::Yellow, Rgb888::YELLOW),
+        (
+            epd_waveshare::color::OctColor::Orange,
+            Rgb888::new(255, 128, 0),
+        ),
+    ]);
+
+    /// Black and white inks of ACeP displays.
+    pub(crate) const ACHROMATIC: Self = Self::new(&[
+        (epd_waveshare::color::OctColor::Black, Rgb888::BLACK),
+        (epd_waveshare::color::OctColor::White, Rgb888::WHITE),
+    ]);
+
+    /// The colored inks of ACeP displays, without black and white.
+    pub(crate) const CHROMATIC: Self = Self::new(&[
+        (epd_waveshare::color::OctColor::Green, Rgb888::GREEN),
+        (epd_waveshare::color::OctColor::Blue, Rgb888::BLUE),
+        (epd_waveshare::color::OctColor::Red, Rgb888::RED),
+        (epd_waveshare::color::OctColor::Yellow, Rgb888::YELLOW),
+        (
+            epd_waveshare::color::OctColor::Orange,
+            Rgb888::new(255, 128, 0),
+        ),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::pixelcolor::BinaryColor;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Rgb888::BLACK, BinaryColor::Off)]
+    #[case(Rgb888::WHITE, BinaryColor::On)]
+    #[case(Rgb888::new(100, 100, 100), BinaryColor::Off)]
+    #[case(Rgb888::new(170, 170, 170), BinaryColor::On)]
+    #[case(Rgb888::YELLOW, BinaryColor::On)]
+    #[case(Rgb888::BLUE, BinaryColor::Off)]
+    fn nearest_binary_color(#[case] color: Rgb888, #[case] expected: BinaryColor) {
+        assert_eq!(InkPalette::<BinaryColor>::DEFAULT.nearest(color), expected);
+    }
+
+    #[rstest]
+    #[case(Rgb888::new(128, 128, 128), 64, BinaryColor::On)]
+    #[case(Rgb888::new(128, 128, 128), 192, BinaryColor::Off)]
+    #[case(Rgb888::WHITE, 255, BinaryColor::On)]
+    #[case(Rgb888::BLACK, 1, BinaryColor::Off)]
+    fn dithered_binary_color(
+        #[case] color: Rgb888,
+        #[case] threshold: u8,
+        #[case] expected: BinaryColor,
+    ) {
+        assert_eq!(
+            InkPalette::<BinaryColor>::DEFAULT.dithered(color, threshold),
+            expected
+        );
+    }
+
+    #[cfg(feature = "epd-weact")]
+    #[rstest]
+    #[case(Rgb888::new(40, 40, 40), weact_studio_epd::Color::Black)]
+    #[case(Rgb888::new(220, 220, 220), weact_studio_epd::Color::White)]
+    #[case(Rgb888::CYAN, weact_studio_epd::Color::White)]
+    fn nearest_weact_color(#[case] color: Rgb888, #[case] expected: weact_studio_epd::Color) {
+        assert_eq!(
+            InkPalette::<weact_studio_epd::Color>::DEFAULT.nearest(color),
+            expected
+        );
+    }
+
+    #[cfg(feature = "epd-weact")]
+    #[rstest]
+    #[case(Rgb888::RED, weact_studio_epd::TriColor::Red)]
+    #[case(Rgb888::new(255, 127, 127), weact_studio_epd::TriColor::Red)]
+    #[case(Rgb888::new(247, 118, 142), weact_studio_epd::TriColor::Red)]
+    #[case(Rgb888::new(180, 30, 30), weact_studio_epd::TriColor::Red)]
+    #[case(Rgb888::new(100, 0, 0), weact_studio_epd::TriColor::Black)]
+    #[case(Rgb888::new(220, 220, 220), weact_studio_epd::TriColor::White)]
+    #[case(Rgb888::new(30, 30, 40), weact_studio_epd::TriColor::Black)]
+    #[case(Rgb888::BLUE, weact_studio_epd::TriColor::Black)]
+    fn nearest_weact_tricolor(#[case] color: Rgb888, #[case] expected: weact_studio_epd::TriColor) {
+        assert_eq!(
+            InkPalette::<weact_studio_epd::TriColor>::DEFAULT.nearest(color),
+            expected
+        );
+    }
+
+    #[cfg(feature = "epd-waveshare")]
+    #[rstest]
+    #[case(Rgb888::new(40, 40, 40), epd_waveshare::color::Color::Black)]
+    #[case(Rgb888::new(220, 220, 220), epd_waveshare::color::Color::White)]
+    #[case(Rgb888::CYAN, epd_waveshare::color::Color::White)]
+    fn nearest_waveshare_color(
+        #[case] color: Rgb888,
+        #[case] expected: epd_waveshare::color::Color,
+    ) {
+        assert_eq!(
+            InkPalette::<epd_waveshare::color::Color>::DEFAULT.nearest(color),
+            expected
+        );
+    }
+
+    #[cfg(feature = "epd-waveshare")]
+    #[rstest]
+    #[case(
+        InkPalette::<epd_waveshare::color::TriColor>::DEFAULT,
+        Rgb888::new(255, 127, 127),
+        epd_waveshare::color::TriColor::Chromatic
+    )]
+    #[case(
+        InkPalette::<epd_waveshare::color::TriColor>::DEFAULT,
+        Rgb888::new(200, 40, 30),
+        epd_waveshare::color::TriColor::Chromatic
+    )]
+    #[case(
+        InkPalette::<epd_waveshare::color::TriColor>::DEFAULT,
+        Rgb888::YELLOW,
+        epd_waveshare::color::TriColor::White
+    )]
+    #[case(
+        InkPalette::<epd_waveshare::color::TriColor>::YELLOW,
+        Rgb888::new(255, 200, 0),
+        epd_waveshare::color::TriColor::Chromatic
+    )]
+    #[case(InkPalette::YELLOW, Rgb888::RED, epd_waveshare::color::TriColor::Black)]
+    #[case(
+        InkPalette::<epd_waveshare::color::TriColor>::DEFAULT,
+        Rgb888::new(20, 20, 20),
+        epd_waveshare::color::TriColor::Black
+    )]
+    fn nearest_waveshare_tricolor(
+        #[case] palette: InkPalette<epd_waveshare::color::TriColor>,
+        #[case] color: Rgb888,
+        #[case] expected: epd_waveshare::color::TriColor,
+    ) {
+        assert_eq!(palette.nearest(color), expected);
+    }
+
+    #[cfg(feature = "epd-waveshare")]
+    #[rstest]
+    #[case(Rgb888::new(255, 140, 20), epd_waveshare::color::OctColor::Orange)]
+    #[case(Rgb888::new(30, 160, 40), epd_waveshare::color::OctColor::Green)]
+    #[case(Rgb888::new(40, 60, 200), epd_waveshare::color::OctColor::Blue)]
+    #[case(Rgb888::new(250, 230, 60), epd_waveshare::color::OctColor::Yellow)]
+    #[case(Rgb888::new(200, 30, 40), epd_waveshare::color::OctColor::Red)]
+    #[case(Rgb888::new(230, 230, 230), epd_waveshare::color::OctColor::White)]
+    #[case(Rgb888::new(20, 20, 30), epd_waveshare::color::OctColor::Black)]
+    fn nearest_waveshare_octcolor(
+        #[case] color: Rgb888,
+        #[case] expected: epd_waveshare::color::OctColor,
+    ) {
+        assert_eq!(
+            InkPalette::<epd_waveshare::color::OctColor>::DEFAULT.nearest(color),
+            expected
+        );
+    }
+}
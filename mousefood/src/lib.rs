@@ -3,18 +3,67 @@
 
 extern crate alloc;
 
+#[cfg(feature = "framebuffer")]
+mod async_display;
 mod backend;
+mod cell;
+mod color_filter;
+mod color_mapper;
 mod colors;
 mod default_font;
+mod dither;
+#[cfg(any(feature = "epd-weact", feature = "epd-waveshare"))]
+mod epd;
+#[cfg(all(test, any(feature = "epd-weact", feature = "epd-waveshare")))]
+mod epd_mocks;
+#[cfg(feature = "epd-waveshare")]
+mod epd_waveshare;
+#[cfg(feature = "epd-weact")]
+mod epd_weact;
 pub mod error;
 #[cfg(feature = "framebuffer")]
 mod framebuffer;
+#[cfg(feature = "framebuffer")]
+mod indexed_buffer;
+mod ink_palette;
 mod macros;
+#[cfg(feature = "framebuffer")]
+mod memory;
+mod native_palette;
+#[cfg(feature = "framebuffer")]
+mod packed_buffer;
 pub mod prelude;
+mod rectangle;
+#[cfg(any(feature = "epd-weact", feature = "epd-waveshare"))]
+mod refresh_policy;
+mod shared_display;
+mod theme_import;
+mod transform;
 
-pub use backend::{EmbeddedBackend, EmbeddedBackendConfig, TerminalAlignment};
-pub use colors::ColorTheme;
+#[cfg(feature = "framebuffer")]
+pub use async_display::AsyncDisplay;
+pub use backend::{
+    EmbeddedBackend, EmbeddedBackendConfig, FlushCallback, Rotation, TerminalAlignment,
+};
+pub use color_filter::{ChannelOrder, ColorAdjustment, ColorFilter};
+pub use color_mapper::{ColorMapper, DefaultColorMapper};
+pub use colors::{ColorTheme, TermColorType};
+pub use dither::Dithering;
 pub use embedded_graphics;
+#[cfg(any(feature = "epd-weact", feature = "epd-waveshare"))]
+pub use epd::{EpdDisplay, epd_flush_callback};
+#[cfg(feature = "epd-waveshare")]
+pub use epd_waveshare::WaveshareEpd;
+#[cfg(feature = "epd-weact")]
+pub use epd_weact::WeActEpd;
+#[cfg(feature = "framebuffer")]
+pub use framebuffer::{FramebufferLayout, FramebufferMemory};
+#[cfg(feature = "framebuffer")]
+pub use indexed_buffer::IndexBits;
+pub use ink_palette::InkPalette;
+#[cfg(any(feature = "epd-weact", feature = "epd-waveshare"))]
+pub use refresh_policy::{Refresh, RefreshPolicy};
+pub use shared_display::{DisplayRegion, SharedDisplay};
 
 #[cfg(feature = "fonts")]
 pub use embedded_graphics_unicodefonts as fonts;
@@ -0,0 +1,100 @@
+//! Memory of framebuffers, on the heap or provided by the application.
+
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+
+/// Memory holding the contents of a framebuffer.
+pub(crate) enum Memory<T: 'static> {
+    /// Memory allocated on the heap.
+    Heap(Vec<T>),
+    /// Static memory provided by the application.
+    Static(&'static mut [T]),
+}
+
+impl<T> Deref for Memory<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Self::Heap(data) => data,
+            Self::Static(data) => data,
+        }
+    }
+}
+
+impl<T> DerefMut for Memory<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        match self {
+            Self::Heap(data) => data,
+            Self::Static(data) => data,
+        }
+    }
+}
+
+impl<T: Copy> Memory<T> {
+    /// Uses `memory`, filling its first `len` elements with `value`.
+    ///
+    /// The whole of `memory` is kept, so that the framebuffer can grow into it later.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `memory` holds less than `len` elements.
+    pub(crate) fn from_static(memory: &'static mut [T], len: usize, value: T) -> Self {
+        assert!(
+            memory.len() >= len,
+            "framebuffer memory holds {} elements, {len} are needed",
+            memory.len()
+        );
+        memory[..len].fill(value);
+        Self::Static(memory)
+    }
+
+    /// Makes the memory hold at least `len` elements, growing memory on the heap with `value`.
+    ///
+    /// Returns the number of elements of static memory holding less than `len` elements
+    /// as the error.
+    pub(crate) fn reserve(&mut self, len: usize, value: T) -> Result<(), usize> {
+        match self {
+            Self::Heap(data) if data.len() < len => data.resize(len, value),
+            Self::Static(data) if data.len() < len => return Err(data.len()),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use rstest::rstest;
+
+    #[rstest]
+    fn fills_static_memory() {
+        let memory = Vec::leak(vec![1u8; 6]);
+        let data = Memory::from_static(memory, 4, 0);
+        assert_eq!(*data, [0, 0, 0, 0, 1, 1]);
+    }
+
+    #[rstest]
+    fn grows_heap_memory() {
+        let mut data = Memory::Heap(vec![0u8; 2]);
+        assert_eq!(data.reserve(4, 1), Ok(()));
+        assert_eq!(*data, [0, 0, 1, 1]);
+    }
+
+    #[rstest]
+    fn rejects_growing_static_memory() {
+        let memory = Vec::leak(vec![0u8; 3]);
+        let mut data = Memory::from_static(memory, 2, 0);
+        assert_eq!(data.reserve(3, 1), Ok(()));
+        assert_eq!(data.reserve(4, 1), Err(3));
+    }
+
+    #[rstest]
+    #[should_panic(expected = "framebuffer memory holds 3 elements, 4 are needed")]
+    fn rejects_too_small_memory() {
+        let memory = Vec::leak(vec![0u8; 3]);
+        Memory::from_static(memory, 4, 0);
+    }
+}
@@ -0,0 +1,251 @@
+//! Display colors of the terminal colors, computed once per theme.
+//!
+//! Mapping a terminal color to a display color goes through the [`ColorFilter`](crate::ColorFilter),
+//! the [`ColorTheme`](crate::ColorTheme) and the [`ColorMapper`](crate::ColorMapper), which is
+//! too slow to do for every cell on microcontrollers without an FPU.
+//! [`NativePalette`] keeps the results for `Color::Reset` and the 16 named colors, and caches a few
+//! `Color::Rgb` and `Color::Indexed` colors.
+
+use embedded_graphics::pixelcolor::PixelColor;
+use ratatui_core::style::Color;
+
+use crate::colors::TermColorType;
+
+/// Number of `Color::Rgb` and `Color::Indexed` colors kept in the cache.
+const CACHE_SIZE: usize = 8;
+
+/// Number of colors precomputed per role: `Color::Reset` and the 16 named colors.
+const NAMED_COLORS: usize = 17;
+
+/// Display colors of terminal colors.
+pub(crate) struct NativePalette<C> {
+    /// Display colors of the named colors, indexed by role and [`named_index`].
+    named: [[C; NAMED_COLORS]; 2],
+    /// Recently used `Color::Rgb` and `Color::Indexed` colors.
+    cache: [Option<(Color, TermColorType, C)>; CACHE_SIZE],
+    /// Cache slot replaced by the next insertion.
+    next: usize,
+}
+
+impl<C: PixelColor> NativePalette<C> {
+    /// Creates a palette, computing the display colors of the named colors with `map`.
+    pub(crate) fn new(map: impl Fn(Color, TermColorType) -> C) -> Self {
+        let named = [TermColorType::Foreground, TermColorType::Background]
+            .map(|color_type| NAMED.map(|color| map(color, color_type)));
+        Self {
+            named,
+            cache: [None; CACHE_SIZE],
+            next: 0,
+        }
+    }
+
+    /// Returns the display colors of the named colors, of both roles.
+    #[cfg(feature = "framebuffer")]
+    pub(crate) fn named_colors(&self) -> impl Iterator<Item = C> + '_ {
+        self.named.iter().flatten().copied()
+    }
+
+    /// Returns the display color of `color`, or `None` if it isn't in the palette.
+    pub(crate) fn get(&self, color: Color, color_type: TermColorType) -> Option<C> {
+        match named_index(color) {
+            Some(index) => Some(self.named[color_type as usize][index]),
+            None => self
+                .cache
+                .iter()
+                .flatten()
+                .find(|(c, t, _)| *c == color && *t == color_type)
+                .map(|&(_, _, display_color)| display_color),
+        }
+    }
+
+    /// Returns the display color of `color`, computing and caching it with `map` if it isn't in
+    /// the palette.
+    pub(crate) fn get_or_insert_with(
+        &mut self,
+        color: Color,
+        color_type: TermColorType,
+        map: impl FnOnce() -> C,
+    ) -> C {
+        match self.get(color, color_type) {
+            Some(display_color) => display_color,
+            None => {
+                let display_color = map();
+                self.insert(color, color_type, display_color);
+                display_color
+            }
+        }
+    }
+
+    /// Caches the display color of a `Color::Rgb` or `Color::Indexed` color,
+    /// replacing the least recently inserted one if the cache is full.
+    fn insert(&mut self, color: Color, color_type: TermColorType, display_color: C) {
+        self.cache[self.next] = Some((color, color_type, display_color));
+        self.next = (self.next + 1) % CACHE_SIZE;
+    }
+}
+
+/// Colors precomputed by the palette, in the order of [`named_index`].
+const NAMED: [Color; NAMED_COLORS] = [
+    Color::Reset,
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Returns the index of a named color in [`NAMED`].
+fn named_index(color: Color) -> Option<usize> {
+    Some(match color {
+        Color::Reset => 0,
+        Color::Black => 1,
+        Color::Red => 2,
+        Color::Green => 3,
+        Color::Yellow => 4,
+        Color::Blue => 5,
+        Color::Magenta => 6,
+        Color::Cyan => 7,
+        Color::Gray => 8,
+        Color::DarkGray => 9,
+        Color::LightRed => 10,
+        Color::LightGreen => 11,
+        Color::LightYellow => 12,
+        Color::LightBlue => 13,
+        Color::LightMagenta => 14,
+        Color::LightCyan => 15,
+        Color::White => 16,
+        Color::Rgb(..) | Color::Indexed(_) => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    use core::cell::Cell;
+    use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+
+    use crate::colors::ColorTheme;
+
+    fn palette(theme: &ColorTheme) -> NativePalette<Rgb888> {
+        NativePalette::new(|color, color_type| theme.resolve(color, color_type))
+    }
+
+    #[rstest]
+    fn indexes_named_colors() {
+        for (index, color) in NAMED.into_iter().enumerate() {
+            assert_eq!(named_index(color), Some(index));
+        }
+        assert_eq!(named_index(Color::Rgb(1, 2, 3)), None);
+        assert_eq!(named_index(Color::Indexed(1)), None);
+    }
+
+    #[rstest]
+    fn precomputes_named_colors() {
+        let theme = ColorTheme::ansi();
+        let calls = Cell::new(0);
+        let palette = NativePalette::new(|color, color_type| {
+            calls.set(calls.get() + 1);
+            theme.resolve(color, color_type)
+        });
+        assert_eq!(calls.get(), 2 * NAMED_COLORS);
+
+        for color_type in [TermColorType::Foreground, TermColorType::Background] {
+            for color in NAMED {
+                assert_eq!(
+                    palette.get(color, color_type),
+                    Some(theme.resolve(color, color_type))
+                );
+            }
+        }
+        assert_eq!(calls.get(), 2 * NAMED_COLORS);
+    }
+
+    #[rstest]
+    fn distinguishes_reset_by_role() {
+        let theme = ColorTheme {
+            foreground: Rgb888::WHITE,
+            background: Rgb888::BLUE,
+            ..ColorTheme::ansi()
+        };
+        let palette = palette(&theme);
+        assert_eq!(
+            palette.get(Color::Reset, TermColorType::Foreground),
+            Some(Rgb888::WHITE)
+        );
+        assert_eq!(
+            palette.get(Color::Reset, TermColorType::Background),
+            Some(Rgb888::BLUE)
+        );
+    }
+
+    #[rstest]
+    #[case(Color::Rgb(1, 2, 3))]
+    #[case(Color::Indexed(200))]
+    fn caches_other_colors(#[case] color: Color) {
+        let theme = ColorTheme::ansi();
+        let mut palette = palette(&theme);
+        assert_eq!(palette.get(color, TermColorType::Foreground), None);
+
+        palette.insert(color, TermColorType::Foreground, Rgb888::RED);
+        assert_eq!(
+            palette.get(color, TermColorType::Foreground),
+            Some(Rgb888::RED)
+        );
+        assert_eq!(palette.get(color, TermColorType::Background), None);
+    }
+
+    #[rstest]
+    fn maps_missing_colors_once() {
+        let theme = ColorTheme::ansi();
+        let mut palette = palette(&theme);
+        let calls = Cell::new(0);
+        let map = || {
+            calls.set(calls.get() + 1);
+            Rgb888::new(1, 2, 3)
+        };
+
+        for _ in 0..3 {
+            assert_eq!(
+                palette.get_or_insert_with(Color::Rgb(1, 2, 3), TermColorType::Foreground, map),
+                Rgb888::new(1, 2, 3)
+            );
+        }
+        assert_eq!(calls.get(), 1);
+
+        palette.get_or_insert_with(Color::Red, TermColorType::Foreground, map);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[rstest]
+    fn evicts_oldest_cached_color() {
+        let theme = ColorTheme::ansi();
+        let mut palette = palette(&theme);
+        for i in 0..=CACHE_SIZE as u8 {
+            palette.insert(Color::Rgb(i, 0, 0), TermColorType::Foreground, Rgb888::RED);
+        }
+
+        assert_eq!(
+            palette.get(Color::Rgb(0, 0, 0), TermColorType::Foreground),
+            None
+        );
+        for i in 1..=CACHE_SIZE as u8 {
+            assert_eq!(
+                palette.get(Color::Rgb(i, 0, 0), TermColorType::Foreground),
+                Some(Rgb888::RED)
+            );
+        }
+    }
+}
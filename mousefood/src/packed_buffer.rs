@@ -0,0 +1,217 @@
+//! Framebuffer storage packing the raw data of the pixel colors into bytes.
+//!
+//! The [packed layout](crate::FramebufferLayout::packed) stores 8 pixels per byte for
+//! `BinaryColor`, 2 or 4 bits per pixel for `Gray4` and `Gray2`, big-endian `u16` for `Rgb565`.
+//! Its bytes can be sent to the display as they are, e.g. over SPI.
+
+use embedded_graphics::geometry::Size;
+use embedded_graphics::pixelcolor::PixelColor;
+use embedded_graphics::pixelcolor::raw::RawData;
+
+/// Conversion of pixel colors to their raw data, packed into bytes.
+///
+/// Pixels are stored row by row. Colors of less than 8 bits are packed most significant bits
+/// first, and every row starts on a new byte. Colors of 8 bits or more are stored big-endian.
+/// The raw data is the one of embedded-graphics, e.g. `BinaryColor::On` is stored as `1`.
+pub(crate) struct Packing<C> {
+    /// Number of bits of a pixel.
+    bits: usize,
+    to_raw: fn(C) -> u32,
+    from_raw: fn(u32) -> C,
+}
+
+impl<C> Clone for Packing<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Packing<C> {}
+
+impl<C: PixelColor> Packing<C> {
+    /// Creates the packing of the raw data of `C`.
+    pub(crate) fn new() -> Self
+    where
+        C: From<C::Raw>,
+        C::Raw: From<C>,
+        <C::Raw as RawData>::Storage: Into<u32>,
+    {
+        Self {
+            bits: C::Raw::BITS_PER_PIXEL,
+            to_raw: |color| C::Raw::from(color).into_inner().into(),
+            from_raw: |value| C::from(C::Raw::from_u32(value)),
+        }
+    }
+
+    /// Returns the number of bytes of a row of `width` pixels.
+    pub(crate) fn stride(&self, width: u32) -> usize {
+        (width as usize * self.bits).div_ceil(8)
+    }
+
+    /// Returns the number of bytes of `size` pixels.
+    pub(crate) fn byte_count(&self, size: Size) -> usize {
+        self.stride(size.width) * size.height as usize
+    }
+
+    /// Returns the index of the byte holding the pixel at column `x` of row `y`, in rows of
+    /// `stride` bytes, and the shift of its bits within that byte.
+    fn position(&self, stride: usize, x: usize, y: usize) -> (usize, u32) {
+        let bit = x * self.bits;
+        let index = y * stride + bit / 8;
+        let shift = if self.bits < 8 {
+            8 - self.bits - bit % 8
+        } else {
+            0
+        };
+        (index, shift as u32)
+    }
+
+    /// Returns the color of the pixel at column `x` of row `y`, in rows of `stride` bytes.
+    pub(crate) fn get(&self, data: &[u8], stride: usize, x: usize, y: usize) -> C {
+        let (index, shift) = self.position(stride, x, y);
+        let value = if self.bits < 8 {
+            u32::from(data[index] >> shift) & ((1 << self.bits) - 1)
+        } else {
+            data[index..index + self.bits / 8]
+                .iter()
+                .fold(0, |value, &byte| value << 8 | u32::from(byte))
+        };
+        (self.from_raw)(value)
+    }
+
+    /// Sets the pixel at column `x` of row `y`, in rows of `stride` bytes.
+    pub(crate) fn set(&self, data: &mut [u8], stride: usize, x: usize, y: usize, color: C) {
+        let (index, shift) = self.position(stride, x, y);
+        let value = (self.to_raw)(color);
+        if self.bits < 8 {
+            let mask = (((1u32 << self.bits) - 1) << shift) as u8;
+            data[index] = data[index] & !mask | ((value << shift) as u8 & mask);
+        } else {
+            let bytes = self.bits / 8;
+            data[index..index + bytes].copy_from_slice(&value.to_be_bytes()[4 - bytes..]);
+        }
+    }
+
+    /// Sets all pixels of `data` to `color`.
+    pub(crate) fn fill(&self, data: &mut [u8], color: C) {
+        let value = (self.to_raw)(color);
+        if self.bits < 8 {
+            let byte = (0..8 / self.bits).fold(0u8, |byte, _| byte << self.bits | value as u8);
+            data.fill(byte);
+        } else {
+            let bytes = self.bits / 8;
+            let raw = &value.to_be_bytes()[4 - bytes..];
+            for pixel in data.chunks_exact_mut(bytes) {
+                pixel.copy_from_slice(raw);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use rstest::rstest;
+
+    use embedded_graphics::pixelcolor::{BinaryColor, Gray2, Gray4, Gray8, Rgb565, Rgb888};
+    use embedded_graphics::prelude::*;
+
+    /// Packs `pixels` into rows of `width` pixels filled with `background`.
+    fn pack<C>(width: u32, height: u32, background: C, pixels: &[(usize, usize, C)]) -> Vec<u8>
+    where
+        C: PixelColor + From<C::Raw>,
+        C::Raw: From<C>,
+        <C::Raw as RawData>::Storage: Into<u32>,
+    {
+        let packing = Packing::<C>::new();
+        let stride = packing.stride(width);
+        let mut data = vec![0; packing.byte_count(Size::new(width, height))];
+        packing.fill(&mut data, background);
+        for &(x, y, color) in pixels {
+            packing.set(&mut data, stride, x, y, color);
+        }
+        data
+    }
+
+    #[rstest]
+    fn packs_binary_colors() {
+        let on = BinaryColor::On;
+        assert_eq!(
+            pack(
+                10,
+                2,
+                BinaryColor::Off,
+                &[(0, 0, on), (9, 0, on), (1, 1, on)]
+            ),
+            [0b1000_0000, 0b0100_0000, 0b0100_0000, 0b0000_0000]
+        );
+    }
+
+    #[rstest]
+    fn packs_gray_colors() {
+        let pixels = [
+            (0, 0, Gray2::new(3)),
+            (1, 0, Gray2::new(1)),
+            (4, 0, Gray2::new(2)),
+        ];
+        assert_eq!(
+            pack(5, 1, Gray2::new(0), &pixels),
+            [0b1101_0000, 0b1000_0000]
+        );
+        assert_eq!(
+            pack(3, 1, Gray4::new(1), &[(1, 0, Gray4::new(0xA))]),
+            [0x1A, 0x11]
+        );
+    }
+
+    #[rstest]
+    fn stores_big_endian_colors() {
+        assert_eq!(
+            pack(
+                2,
+                1,
+                Rgb565::BLACK,
+                &[(1, 0, Rgb565::new(0x1f, 0x00, 0x0a))]
+            ),
+            [0x00, 0x00, 0b1111_1000, 0b0000_1010]
+        );
+        assert_eq!(pack(1, 1, Rgb888::new(1, 2, 3), &[]), [1, 2, 3]);
+    }
+
+    #[rstest]
+    #[case(BinaryColor::On, 0xFF)]
+    #[case(BinaryColor::Off, 0x00)]
+    fn fills_binary_colors(#[case] color: BinaryColor, #[case] byte: u8) {
+        assert_eq!(pack(12, 3, color, &[]), [byte; 6]);
+    }
+
+    #[rstest]
+    fn fills_gray_colors() {
+        assert_eq!(pack(4, 1, Gray2::new(2), &[]), [0b1010_1010]);
+    }
+
+    #[rstest]
+    fn reads_pixels() {
+        let packing = Packing::<Gray4>::new();
+        let data = pack(3, 2, Gray4::new(0), &[(2, 1, Gray4::new(7))]);
+        assert_eq!(packing.get(&data, 2, 2, 1), Gray4::new(7));
+        assert_eq!(packing.get(&data, 2, 1, 1), Gray4::new(0));
+
+        let packing = Packing::<Rgb565>::new();
+        let data = pack(3, 2, Rgb565::BLACK, &[(1, 0, Rgb565::CYAN)]);
+        assert_eq!(packing.get(&data, 6, 1, 0), Rgb565::CYAN);
+    }
+
+    #[rstest]
+    #[case(Size::new(296, 128), 4736)]
+    #[case(Size::new(250, 122), 3904)]
+    fn counts_bytes(#[case] size: Size, #[case] byte_count: usize) {
+        assert_eq!(Packing::<BinaryColor>::new().byte_count(size), byte_count);
+        assert_eq!(
+            Packing::<Gray8>::new().byte_count(size),
+            size.width as usize * size.height as usize
+        );
+    }
+}
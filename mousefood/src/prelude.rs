@@ -13,5 +13,5 @@
 pub use crate::ColorTheme;
 pub use crate::backend::{EmbeddedBackend, EmbeddedBackendConfig};
 pub use embedded_graphics::pixelcolor::{
-    Bgr555, Bgr565, Bgr666, Bgr888, Rgb555, Rgb565, Rgb666, Rgb888,
+    Bgr555, Bgr565, Bgr666, Bgr888, Gray2, Gray4, Gray8, Rgb555, Rgb565, Rgb666, Rgb888,
 };
@@ -0,0 +1,48 @@
+//! Helpers for rectangles of pixels.
+
+use embedded_graphics::primitives::Rectangle;
+
+/// Returns the smallest rectangle containing both `a` and `b`.
+///
+/// Empty rectangles are ignored.
+pub(crate) fn envelope(a: &Rectangle, b: &Rectangle) -> Rectangle {
+    match (a.bottom_right(), b.bottom_right()) {
+        (None, _) => *b,
+        (_, None) => *a,
+        (Some(a_bottom_right), Some(b_bottom_right)) => Rectangle::with_corners(
+            a.top_left.component_min(b.top_left),
+            a_bottom_right.component_max(b_bottom_right),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::geometry::{Point, Size};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        Rectangle::new(Point::new(1, 2), Size::new(3, 4)),
+        Rectangle::new(Point::new(5, 0), Size::new(2, 2)),
+        Rectangle::new(Point::new(1, 0), Size::new(6, 6))
+    )]
+    #[case(
+        Rectangle::new(Point::new(1, 2), Size::new(3, 4)),
+        Rectangle::new(Point::new(9, 9), Size::zero()),
+        Rectangle::new(Point::new(1, 2), Size::new(3, 4))
+    )]
+    #[case(
+        Rectangle::new(Point::new(9, 9), Size::zero()),
+        Rectangle::new(Point::new(1, 2), Size::new(3, 4)),
+        Rectangle::new(Point::new(1, 2), Size::new(3, 4))
+    )]
+    fn envelops_rectangles(
+        #[case] a: Rectangle,
+        #[case] b: Rectangle,
+        #[case] expected: Rectangle,
+    ) {
+        assert_eq!(envelope(&a, &b), expected);
+    }
+}
@@ -0,0 +1,251 @@
+//! Refresh policy for e-paper displays.
+//!
+//! Partial refreshes are fast but leave ghosting behind, full refreshes clear it but take seconds
+//! and flash the panel. [`RefreshPolicy`] decides which one a flush needs.
+
+use core::time::Duration;
+
+/// Kind of refresh chosen by a [`RefreshPolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Refresh {
+    /// Refresh the whole panel, clearing ghosting.
+    Full,
+    /// Refresh only the changed pixels.
+    Partial,
+    /// Don't refresh yet, the previous refresh is too recent.
+    ///
+    /// [`RefreshPolicy::poll`] returns the refresh once it is due.
+    Deferred,
+}
+
+/// Chooses between full and partial refreshes of an e-paper display.
+///
+/// A full refresh is done:
+/// - on the first flush, as the content of the panel is unknown,
+/// - after [`full_every`](Self::full_every) partial refreshes,
+/// - when [`full_after`](Self::full_after) elapsed since the last full refresh,
+/// - after [`request_full`](Self::request_full).
+///
+/// Flushes arriving within [`min_interval`](Self::min_interval) of the previous refresh are
+/// coalesced: they are [deferred](Refresh::Deferred) and a single refresh is returned by
+/// [`poll`](Self::poll) once the interval elapsed.
+///
+/// Time is passed by the caller as the duration since any fixed point, e.g. the boot of the
+/// device.
+///
+/// ```rust
+/// use core::time::Duration;
+/// use mousefood::{Refresh, RefreshPolicy};
+///
+/// let mut policy = RefreshPolicy::new()
+///     .full_every(10)
+///     .full_after(Duration::from_secs(600))
+///     .min_interval(Duration::from_millis(500));
+///
+/// assert_eq!(policy.on_flush(Duration::ZERO), Refresh::Full);
+/// assert_eq!(policy.on_flush(Duration::from_secs(1)), Refresh::Partial);
+/// assert_eq!(policy.on_flush(Duration::from_millis(1200)), Refresh::Deferred);
+/// assert_eq!(policy.poll(Duration::from_millis(1400)), None);
+/// assert_eq!(policy.poll(Duration::from_millis(1500)), Some(Refresh::Partial));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefreshPolicy {
+    full_every: Option<u32>,
+    full_after: Option<Duration>,
+    min_interval: Duration,
+
+    /// Partial refreshes since the last full refresh.
+    partials: u32,
+    /// Time of the last full refresh.
+    last_full: Option<Duration>,
+    /// Time of the last refresh.
+    last_refresh: Option<Duration>,
+    /// Whether a deferred flush is waiting.
+    pending: bool,
+    /// Whether the next refresh must be a full refresh.
+    force_full: bool,
+}
+
+impl RefreshPolicy {
+    /// Creates a policy which always does partial refreshes after the first full refresh.
+    pub const fn new() -> Self {
+        Self {
+            full_every: None,
+            full_after: None,
+            min_interval: Duration::ZERO,
+            partials: 0,
+            last_full: None,
+            last_refresh: None,
+            pending: false,
+            force_full: false,
+        }
+    }
+
+    /// Does a full refresh after `partials` partial refreshes.
+    ///
+    /// With `0`, every refresh is a full refresh.
+    #[must_use]
+    pub const fn full_every(mut self, partials: u32) -> Self {
+        self.full_every = Some(partials);
+        self
+    }
+
+    /// Does a full refresh when `duration` elapsed since the last full refresh.
+    #[must_use]
+    pub const fn full_after(mut self, duration: Duration) -> Self {
+        self.full_after = Some(duration);
+        self
+    }
+
+    /// Coalesces flushes arriving within `interval` of the previous refresh,
+    /// e.g. the duration of a partial refresh of the panel.
+    #[must_use]
+    pub const fn min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// Makes the next refresh a full refresh, e.g. after the display woke up.
+    pub fn request_full(&mut self) {
+        self.force_full = true;
+    }
+
+    /// Returns whether a deferred flush is waiting for [`poll`](Self::poll).
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Returns the refresh needed by a flush at `now`.
+    pub fn on_flush(&mut self, now: Duration) -> Refresh {
+        if self.is_too_soon(now) {
+            self.pending = true;
+            return Refresh::Deferred;
+        }
+        self.refresh(now)
+    }
+
+    /// Returns the refresh of a deferred flush once it is due, or `None`.
+    ///
+    /// Call it periodically, e.g. from the event loop, so that the last of a burst of flushes
+    /// reaches the display.
+    pub fn poll(&mut self, now: Duration) -> Option<Refresh> {
+        if !self.pending || self.is_too_soon(now) {
+            return None;
+        }
+        Some(self.refresh(now))
+    }
+
+    fn is_too_soon(&self, now: Duration) -> bool {
+        self.last_refresh
+            .is_some_and(|last| now.saturating_sub(last) < self.min_interval)
+    }
+
+    fn needs_full(&self, now: Duration) -> bool {
+        let Some(last_full) = self.last_full else {
+            return true;
+        };
+        self.force_full
+            || self.full_every.is_some_and(|n| self.partials >= n)
+            || self
+                .full_after
+                .is_some_and(|duration| now.saturating_sub(last_full) >= duration)
+    }
+
+    fn refresh(&mut self, now: Duration) -> Refresh {
+        let refresh = if self.needs_full(now) {
+            self.partials = 0;
+            self.last_full = Some(now);
+            self.force_full = false;
+            Refresh::Full
+        } else {
+            self.partials += 1;
+            Refresh::Partial
+        };
+        self.last_refresh = Some(now);
+        self.pending = false;
+        refresh
+    }
+}
+
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[rstest]
+    fn starts_with_full_refresh() {
+        let mut policy = RefreshPolicy::new();
+        assert_eq!(policy.on_flush(secs(0)), Refresh::Full);
+        for t in 1..10 {
+            assert_eq!(policy.on_flush(secs(t)), Refresh::Partial);
+        }
+    }
+
+    #[rstest]
+    #[case(0, [Refresh::Full, Refresh::Full, Refresh::Full, Refresh::Full, Refresh::Full])]
+    #[case(1, [Refresh::Full, Refresh::Partial, Refresh::Full, Refresh::Partial, Refresh::Full])]
+    #[case(3, [Refresh::Full, Refresh::Partial, Refresh::Partial, Refresh::Partial, Refresh::Full])]
+    fn refreshes_fully_every_n_partials(#[case] n: u32, #[case] expected: [Refresh; 5]) {
+        let mut policy = RefreshPolicy::new().full_every(n);
+        let refreshes: [Refresh; 5] = core::array::from_fn(|t| policy.on_flush(secs(t as u64)));
+        assert_eq!(refreshes, expected);
+    }
+
+    #[rstest]
+    fn refreshes_fully_after_duration() {
+        let mut policy = RefreshPolicy::new().full_after(secs(60));
+        assert_eq!(policy.on_flush(secs(0)), Refresh::Full);
+        assert_eq!(policy.on_flush(secs(30)), Refresh::Partial);
+        assert_eq!(policy.on_flush(secs(59)), Refresh::Partial);
+        assert_eq!(policy.on_flush(secs(60)), Refresh::Full);
+        assert_eq!(policy.on_flush(secs(61)), Refresh::Partial);
+    }
+
+    #[rstest]
+    fn refreshes_fully_on_request() {
+        let mut policy = RefreshPolicy::new();
+        assert_eq!(policy.on_flush(secs(0)), Refresh::Full);
+        assert_eq!(policy.on_flush(secs(1)), Refresh::Partial);
+        policy.request_full();
+        assert_eq!(policy.on_flush(secs(2)), Refresh::Full);
+        assert_eq!(policy.on_flush(secs(3)), Refresh::Partial);
+    }
+
+    #[rstest]
+    fn coalesces_fast_flushes() {
+        let ms = Duration::from_millis;
+        let mut policy = RefreshPolicy::new().min_interval(ms(500));
+        assert_eq!(policy.on_flush(ms(0)), Refresh::Full);
+        assert!(!policy.is_pending());
+        assert_eq!(policy.poll(ms(1000)), None);
+
+        assert_eq!(policy.on_flush(ms(100)), Refresh::Deferred);
+        assert_eq!(policy.on_flush(ms(200)), Refresh::Deferred);
+        assert!(policy.is_pending());
+        assert_eq!(policy.poll(ms(499)), None);
+        assert_eq!(policy.poll(ms(500)), Some(Refresh::Partial));
+        assert!(!policy.is_pending());
+        assert_eq!(policy.poll(ms(2000)), None);
+
+        assert_eq!(policy.on_flush(ms(1000)), Refresh::Partial);
+    }
+
+    #[rstest]
+    fn deferred_flush_can_be_full() {
+        let mut policy = RefreshPolicy::new().full_every(1).min_interval(secs(1));
+        assert_eq!(policy.on_flush(secs(0)), Refresh::Full);
+        assert_eq!(policy.on_flush(secs(1)), Refresh::Partial);
+        assert_eq!(policy.on_flush(secs(1)), Refresh::Deferred);
+        assert_eq!(policy.poll(secs(2)), Some(Refresh::Full));
+    }
+}
@@ -0,0 +1,330 @@
+//! # Display shared between multiple terminals
+//!
+//! [`EmbeddedBackend`](crate::EmbeddedBackend) mutably borrows its display, so only one backend
+//! can exist per display. [`SharedDisplay`] takes ownership of the display and hands out
+//! [`DisplayRegion`]s: draw targets bound to a part of the display, each of which can be used by
+//! its own backend.
+
+use alloc::rc::Rc;
+use core::cell::{RefCell, RefMut};
+
+use embedded_graphics::Pixel;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Dimensions, Point};
+use embedded_graphics::primitives::{PointsIter, Rectangle};
+
+use crate::rectangle::envelope;
+
+struct Shared<D> {
+    display: D,
+    dirty_area: Option<Rectangle>,
+}
+
+impl<D> Shared<D> {
+    fn mark_dirty(&mut self, area: Rectangle) {
+        self.dirty_area = Some(match self.dirty_area {
+            None => area,
+            Some(dirty_area) => envelope(&dirty_area, &area),
+        });
+    }
+}
+
+/// A display shared between multiple terminals.
+///
+/// # Examples
+///
+/// ```rust
+/// use mousefood::embedded_graphics::{mock_display::MockDisplay, pixelcolor::Rgb888};
+/// use mousefood::embedded_graphics::{prelude::*, primitives::Rectangle};
+/// use mousefood::prelude::*;
+/// use mousefood::SharedDisplay;
+/// use ratatui::Terminal;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let shared = SharedDisplay::new(MockDisplay::<Rgb888>::new());
+///     let mut top = shared.region(Rectangle::new(Point::new(0, 0), Size::new(64, 32)));
+///     let mut bottom = shared.region(Rectangle::new(Point::new(0, 32), Size::new(64, 32)));
+///
+///     let mut top_terminal = Terminal::new(EmbeddedBackend::new(&mut top, Default::default()))?;
+///     let mut bottom_terminal =
+///         Terminal::new(EmbeddedBackend::new(&mut bottom, Default::default()))?;
+///
+///     top_terminal.draw(|frame| frame.render_widget("Logs", frame.area()))?;
+///     bottom_terminal.draw(|frame| frame.render_widget("Controls", frame.area()))?;
+///
+///     // Push the changes of both terminals to the physical display at once.
+///     shared.flush(|display, dirty_area| {
+///         // e.g. `window.update(display)` or a partial refresh of `dirty_area`
+///     });
+///     Ok(())
+/// }
+/// ```
+pub struct SharedDisplay<D> {
+    shared: Rc<RefCell<Shared<D>>>,
+}
+
+impl<D: DrawTarget> SharedDisplay<D> {
+    /// Takes ownership of the display so it can be shared.
+    pub fn new(display: D) -> Self {
+        Self {
+            shared: Rc::new(RefCell::new(Shared {
+                display,
+                dirty_area: None,
+            })),
+        }
+    }
+
+    /// Creates a draw target bound to `area` of the display.
+    ///
+    /// The region uses display coordinates and discards pixels outside of `area`.
+    pub fn region(&self, area: Rectangle) -> DisplayRegion<D> {
+        let area = self
+            .shared
+            .borrow()
+            .display
+            .bounding_box()
+            .intersection(&area);
+        DisplayRegion {
+            shared: Rc::clone(&self.shared),
+            area,
+        }
+    }
+
+    /// Mutably borrows the display.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the display is already borrowed, e.g. by a region that is drawing.
+    pub fn display_mut(&self) -> RefMut<'_, D> {
+        RefMut::map(self.shared.borrow_mut(), |shared| &mut shared.display)
+    }
+
+    /// Calls `f` with the display and the area changed by regions since the previous flush.
+    ///
+    /// Returns `None` without calling `f` if no region has drawn since the previous flush.
+    /// This allows flushing the physical display once after several terminals were drawn.
+    pub fn flush<R>(&self, f: impl FnOnce(&mut D, Rectangle) -> R) -> Option<R> {
+        let mut shared = self.shared.borrow_mut();
+        let dirty_area = shared.dirty_area.take()?;
+        Some(f(&mut shared.display, dirty_area))
+    }
+}
+
+impl<D> Clone for SharedDisplay<D> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Rc::clone(&self.shared),
+        }
+    }
+}
+
+/// A draw target bound to a part of a [`SharedDisplay`].
+pub struct DisplayRegion<D> {
+    shared: Rc<RefCell<Shared<D>>>,
+    area: Rectangle,
+}
+
+impl<D: DrawTarget> DisplayRegion<D> {
+    /// Mutably borrows the whole shared display.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the display is already borrowed.
+    pub fn display_mut(&self) -> RefMut<'_, D> {
+        RefMut::map(self.shared.borrow_mut(), |shared| &mut shared.display)
+    }
+
+    /// Returns the shared display this region belongs to.
+    pub fn shared_display(&self) -> SharedDisplay<D> {
+        SharedDisplay {
+            shared: Rc::clone(&self.shared),
+        }
+    }
+}
+
+impl<D> Dimensions for DisplayRegion<D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.area
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for DisplayRegion<D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let area = self.area;
+        let mut shared = self.shared.borrow_mut();
+        // corners of the pixels drawn inside of the region
+        let mut drawn: Option<(Point, Point)> = None;
+        let result = shared.display.draw_iter(
+            pixels
+                .into_iter()
+                .filter(|Pixel(point, _)| area.contains(*point))
+                .inspect(|&Pixel(point, _)| {
+                    drawn = Some(match drawn {
+                        None => (point, point),
+                        Some((top_left, bottom_right)) => (
+                            top_left.component_min(point),
+                            bottom_right.component_max(point),
+                        ),
+                    });
+                }),
+        );
+        if let Some((top_left, bottom_right)) = drawn {
+            shared.mark_dirty(Rectangle::with_corners(top_left, bottom_right));
+        }
+        result
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if self.area.intersection(area) != *area {
+            return self.draw_iter(
+                area.points()
+                    .zip(colors)
+                    .map(|(point, color)| Pixel(point, color)),
+            );
+        }
+        let mut shared = self.shared.borrow_mut();
+        shared.mark_dirty(*area);
+        shared.display.fill_contiguous(area, colors)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = self.area.intersection(area);
+        if area.is_zero_sized() {
+            return Ok(());
+        }
+        let mut shared = self.shared.borrow_mut();
+        shared.mark_dirty(area);
+        shared.display.fill_solid(&area, color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::geometry::Size;
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+    use rstest::rstest;
+
+    fn display() -> MockDisplay<Rgb888> {
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        display
+    }
+
+    #[rstest]
+    fn regions_clip_to_their_area() {
+        let shared = SharedDisplay::new(display());
+        let mut region = shared.region(Rectangle::new(Point::new(1, 1), Size::new(2, 1)));
+
+        region
+            .draw_iter([
+                Pixel(Point::new(1, 1), Rgb888::WHITE),
+                Pixel(Point::new(0, 0), Rgb888::WHITE),
+                Pixel(Point::new(3, 1), Rgb888::WHITE),
+            ])
+            .unwrap();
+        region
+            .fill_solid(
+                &Rectangle::new(Point::new(2, 0), Size::new(2, 2)),
+                Rgb888::RED,
+            )
+            .unwrap();
+
+        shared.display_mut().assert_pattern(&["    ", " WR "]);
+    }
+
+    #[rstest]
+    fn marks_drawn_pixels_dirty() {
+        let shared = SharedDisplay::new(display());
+        let mut region = shared.region(Rectangle::new(Point::new(0, 0), Size::new(8, 8)));
+
+        region
+            .draw_iter([Pixel(Point::new(9, 9), Rgb888::WHITE)])
+            .unwrap();
+        assert_eq!(shared.flush(|_, dirty_area| dirty_area), None);
+
+        region
+            .draw_iter([
+                Pixel(Point::new(2, 5), Rgb888::WHITE),
+                Pixel(Point::new(4, 3), Rgb888::WHITE),
+                Pixel(Point::new(9, 9), Rgb888::WHITE),
+            ])
+            .unwrap();
+        assert_eq!(
+            shared.flush(|_, dirty_area| dirty_area),
+            Some(Rectangle::new(Point::new(2, 3), Size::new(3, 3)))
+        );
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[rstest]
+    fn terminals_share_display() {
+        use crate::backend::{EmbeddedBackend, EmbeddedBackendConfig};
+        use embedded_graphics::mono_font::ascii::FONT_4X6;
+        use ratatui::Terminal;
+        use ratatui::text::Line;
+
+        let shared = SharedDisplay::new(display());
+        let top_area = Rectangle::new(Point::new(0, 0), Size::new(16, 6));
+        let bottom_area = Rectangle::new(Point::new(0, 6), Size::new(16, 6));
+        let mut top = shared.region(top_area);
+        let mut bottom = shared.region(bottom_area);
+
+        let config = || EmbeddedBackendConfig {
+            font_regular: FONT_4X6,
+            ..Default::default()
+        };
+        let mut top_terminal = Terminal::new(EmbeddedBackend::new(&mut top, config())).unwrap();
+        let mut bottom_terminal =
+            Terminal::new(EmbeddedBackend::new(&mut bottom, config())).unwrap();
+        shared.flush(|_, _| ());
+
+        top_terminal
+            .draw(|frame| frame.render_widget(Line::from("Top"), frame.area()))
+            .unwrap();
+        assert_eq!(
+            shared.flush(|_, dirty_area| dirty_area),
+            Some(top_area),
+            "only the top region was drawn"
+        );
+        assert_eq!(shared.flush(|_, dirty_area| dirty_area), None);
+
+        bottom_terminal
+            .draw(|frame| frame.render_widget(Line::from("Bot"), frame.area()))
+            .unwrap();
+        assert_eq!(shared.flush(|_, dirty_area| dirty_area), Some(bottom_area));
+
+        let mut expected = MockDisplay::<Rgb888>::new();
+        expected.set_allow_overdraw(true);
+        for (text, y) in [("Top", 0), ("Bot", 6)] {
+            use embedded_graphics::Drawable;
+            use embedded_graphics::mono_font::MonoTextStyle;
+            use embedded_graphics::text::{Baseline, Text};
+            expected
+                .fill_solid(
+                    &Rectangle::new(Point::new(0, y), Size::new(16, 6)),
+                    Rgb888::BLACK,
+                )
+                .unwrap();
+            Text::with_baseline(
+                text,
+                Point::new(0, y),
+                MonoTextStyle::new(&FONT_4X6, Rgb888::WHITE),
+                Baseline::Top,
+            )
+            .draw(&mut expected)
+            .unwrap();
+        }
+        shared.display_mut().assert_eq(&expected);
+    }
+}
@@ -0,0 +1,626 @@
+//! Import of [`ColorTheme`]s from common terminal color scheme formats.
+//!
+//! All parsers are `const fn`, so schemes can be embedded at compile time with the
+//! [`color_theme!`](crate::color_theme) macro.
+//!
+//! Colors which are not defined by a scheme keep their [`ColorTheme::ansi`] values.
+
+use embedded_graphics::pixelcolor::Rgb888;
+
+use crate::colors::ColorTheme;
+use crate::error::ThemeParseError;
+
+type Result<T, E = ThemeParseError> = core::result::Result<T, E>;
+
+impl ColorTheme {
+    /// Parses a [base16](https://github.com/tinted-theming/home) color scheme in YAML format.
+    ///
+    /// Both the legacy format with top-level `baseXX` keys and the newer format with a nested
+    /// `palette` are supported. The colors are mapped to ANSI colors like `base16-shell` does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mousefood::ColorTheme;
+    /// use mousefood::embedded_graphics::pixelcolor::Rgb888;
+    ///
+    /// let theme = ColorTheme::from_base16_yaml(
+    ///     r#"
+    ///     scheme: "Default Dark"
+    ///     base00: "181818"
+    ///     base05: "d8d8d8"
+    ///     base08: "ab4642"
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(theme.red, Rgb888::new(0xab, 0x46, 0x42));
+    /// ```
+    pub const fn from_base16_yaml(source: &str) -> Result<Self> {
+        // Theme slot (in `Color::Indexed` order) of every base16 color, as in base16-shell.
+        const SLOTS: [&[usize]; 16] = [
+            &[BACKGROUND, 0],
+            &[],
+            &[],
+            &[8],
+            &[],
+            &[FOREGROUND, 7],
+            &[],
+            &[15],
+            &[1, 9],
+            &[],
+            &[3, 11],
+            &[2, 10],
+            &[6, 14],
+            &[4, 12],
+            &[5, 13],
+            &[],
+        ];
+
+        let mut theme = ColorTheme::ansi();
+        let mut found = false;
+        let mut lines = Lines::new(source);
+        while let Some((line_number, line)) = lines.next() {
+            let line = trim(line);
+            if line.is_empty() || line[0] == b'#' {
+                continue;
+            }
+            let Some((key, value)) = split_once(line, b':') else {
+                continue;
+            };
+            let key = trim_quotes(trim(key));
+            let Some(base) = base16_index(key) else {
+                continue;
+            };
+            let Some(color) = parse_hex_color(first_value(value)) else {
+                return Err(ThemeParseError::InvalidColor { line: line_number });
+            };
+            let slots = SLOTS[base];
+            let mut i = 0;
+            while i < slots.len() {
+                set_slot(&mut theme, slots[i], color);
+                i += 1;
+            }
+            found = true;
+        }
+
+        if found {
+            Ok(theme)
+        } else {
+            Err(ThemeParseError::NoColors)
+        }
+    }
+
+    /// Parses the color sections of an [Alacritty](https://alacritty.org) TOML configuration.
+    ///
+    /// The `[colors.primary]`, `[colors.normal]` and `[colors.bright]` sections are used,
+    /// everything else is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mousefood::ColorTheme;
+    /// use mousefood::embedded_graphics::pixelcolor::Rgb888;
+    ///
+    /// let theme = ColorTheme::from_alacritty_toml(
+    ///     r#"
+    ///     [colors.primary]
+    ///     background = '#1d1f21'
+    ///     foreground = '#c5c8c6'
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(theme.background, Rgb888::new(0x1d, 0x1f, 0x21));
+    /// ```
+    pub const fn from_alacritty_toml(source: &str) -> Result<Self> {
+        const PRIMARY: &[u8] = b"colors.primary";
+        const NORMAL: &[u8] = b"colors.normal";
+        const BRIGHT: &[u8] = b"colors.bright";
+        const NAMES: [&[u8]; 8] = [
+            b"black", b"red", b"green", b"yellow", b"blue", b"magenta", b"cyan", b"white",
+        ];
+
+        let mut theme = ColorTheme::ansi();
+        let mut found = false;
+        // Offset of the current section in `Color::Indexed` order, `None` if it's ignored.
+        let mut section: Option<usize> = None;
+        let mut primary = false;
+        let mut lines = Lines::new(source);
+        while let Some((line_number, line)) = lines.next() {
+            let line = trim(line);
+            if line.is_empty() || line[0] == b'#' {
+                continue;
+            }
+            if line[0] == b'[' {
+                let name = trim(trim_brackets(line));
+                primary = eq(name, PRIMARY);
+                section = if eq(name, NORMAL) {
+                    Some(0)
+                } else if eq(name, BRIGHT) {
+                    Some(8)
+                } else {
+                    None
+                };
+                continue;
+            }
+            let Some((key, value)) = split_once(line, b'=') else {
+                continue;
+            };
+            let key = trim_quotes(trim(key));
+
+            let slot = if primary {
+                if eq(key, b"background") {
+                    Some(BACKGROUND)
+                } else if eq(key, b"foreground") {
+                    Some(FOREGROUND)
+                } else {
+                    None
+                }
+            } else if let Some(offset) = section {
+                let mut slot = None;
+                let mut i = 0;
+                while i < NAMES.len() {
+                    if eq(key, NAMES[i]) {
+                        slot = Some(offset + i);
+                    }
+                    i += 1;
+                }
+                slot
+            } else {
+                None
+            };
+
+            if let Some(slot) = slot {
+                let Some(color) = parse_hex_color(first_value(value)) else {
+                    return Err(ThemeParseError::InvalidColor { line: line_number });
+                };
+                set_slot(&mut theme, slot, color);
+                found = true;
+            }
+        }
+
+        if found {
+            Ok(theme)
+        } else {
+            Err(ThemeParseError::NoColors)
+        }
+    }
+
+    /// Parses the colors defined in an X resources file (e.g. `~/.Xresources`).
+    ///
+    /// Resources ending with `color0` to `color15`, `foreground` and `background` are used,
+    /// e.g. `*.color1: #cc6666` or `URxvt*background: rgb:1d/1f/21`.
+    /// Preprocessor directives such as `#define` are not supported and are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use mousefood::ColorTheme;
+    /// use mousefood::embedded_graphics::pixelcolor::Rgb888;
+    ///
+    /// let theme = ColorTheme::from_xresources("*.color1: #cc6666").unwrap();
+    /// assert_eq!(theme.red, Rgb888::new(0xcc, 0x66, 0x66));
+    /// ```
+    pub const fn from_xresources(source: &str) -> Result<Self> {
+        let mut theme = ColorTheme::ansi();
+        let mut found = false;
+        let mut lines = Lines::new(source);
+        while let Some((line_number, line)) = lines.next() {
+            let line = trim(line);
+            if line.is_empty() || line[0] == b'!' || line[0] == b'#' {
+                continue;
+            }
+            let Some((key, value)) = split_once(line, b':') else {
+                continue;
+            };
+            let Some(slot) = xresources_slot(resource_name(trim(key))) else {
+                continue;
+            };
+            let Some(color) = parse_xresources_color(trim(value)) else {
+                return Err(ThemeParseError::InvalidColor { line: line_number });
+            };
+            set_slot(&mut theme, slot, color);
+            found = true;
+        }
+
+        if found {
+            Ok(theme)
+        } else {
+            Err(ThemeParseError::NoColors)
+        }
+    }
+}
+
+/// Creates a [`ColorTheme`] from a color scheme at compile time.
+///
+/// The scheme format is one of `base16_yaml`, `alacritty_toml` or `xresources`,
+/// followed by a `&'static str` constant expression, e.g. [`include_str!`].
+/// Compilation fails if the scheme is invalid.
+///
+/// # Examples
+///
+/// ```rust
+/// use mousefood::{ColorTheme, color_theme};
+///
+/// const THEME: ColorTheme = color_theme!(xresources, "*.background: #1d1f21");
+/// ```
+///
+/// ```rust,ignore
+/// let theme = mousefood::color_theme!(base16_yaml, include_str!("default-dark.yaml"));
+/// ```
+#[macro_export]
+macro_rules! color_theme {
+    (base16_yaml, $source:expr $(,)?) => {
+        $crate::color_theme!(@parse from_base16_yaml, $source)
+    };
+    (alacritty_toml, $source:expr $(,)?) => {
+        $crate::color_theme!(@parse from_alacritty_toml, $source)
+    };
+    (xresources, $source:expr $(,)?) => {
+        $crate::color_theme!(@parse from_xresources, $source)
+    };
+    (@parse $parser:ident, $source:expr) => {{
+        const THEME: $crate::ColorTheme = match $crate::ColorTheme::$parser($source) {
+            Ok(theme) => theme,
+            Err(error) => panic!("{}", error.message()),
+        };
+        THEME
+    }};
+}
+
+/// Theme slot of the default foreground color.
+const FOREGROUND: usize = 16;
+/// Theme slot of the default background color.
+const BACKGROUND: usize = 17;
+
+/// Sets a theme color by its `Color::Indexed` index, or [`FOREGROUND`]/[`BACKGROUND`].
+const fn set_slot(theme: &mut ColorTheme, slot: usize, color: Rgb888) {
+    match slot {
+        0 => theme.black = color,
+        1 => theme.red = color,
+        2 => theme.green = color,
+        3 => theme.yellow = color,
+        4 => theme.blue = color,
+        5 => theme.magenta = color,
+        6 => theme.cyan = color,
+        7 => theme.gray = color,
+        8 => theme.dark_gray = color,
+        9 => theme.light_red = color,
+        10 => theme.light_green = color,
+        11 => theme.light_yellow = color,
+        12 => theme.light_blue = color,
+        13 => theme.light_magenta = color,
+        14 => theme.light_cyan = color,
+        15 => theme.white = color,
+        FOREGROUND => theme.foreground = color,
+        BACKGROUND => theme.background = color,
+        _ => {}
+    }
+}
+
+/// Iterator-like line splitter usable in `const fn`.
+struct Lines<'a> {
+    rest: &'a [u8],
+    line_number: usize,
+}
+
+impl<'a> Lines<'a> {
+    const fn new(source: &'a str) -> Self {
+        Self {
+            rest: source.as_bytes(),
+            line_number: 0,
+        }
+    }
+
+    /// Returns the next line with its 1-based line number.
+    const fn next(&mut self) -> Option<(usize, &'a [u8])> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        self.line_number += 1;
+        let mut len = 0;
+        while len < self.rest.len() && self.rest[len] != b'\n' {
+            len += 1;
+        }
+        let (line, rest) = self.rest.split_at(len);
+        self.rest = match rest {
+            [_newline, rest @ ..] => rest,
+            [] => rest,
+        };
+        Some((self.line_number, line))
+    }
+}
+
+const fn trim(mut s: &[u8]) -> &[u8] {
+    while let [first, rest @ ..] = s {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        s = rest;
+    }
+    while let [rest @ .., last] = s {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+        s = rest;
+    }
+    s
+}
+
+const fn trim_quotes(s: &[u8]) -> &[u8] {
+    match s {
+        [b'"', inner @ .., b'"'] | [b'\'', inner @ .., b'\''] => inner,
+        _ => s,
+    }
+}
+
+const fn trim_brackets(s: &[u8]) -> &[u8] {
+    match s {
+        [b'[', inner @ .., b']'] => inner,
+        _ => s,
+    }
+}
+
+const fn eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn split_once(s: &[u8], delimiter: u8) -> Option<(&[u8], &[u8])> {
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == delimiter {
+            let (before, after) = s.split_at(i);
+            return Some((before, after.split_at(1).1));
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Returns the first value of `s`: a quoted string, or everything up to the first whitespace.
+///
+/// This strips trailing comments.
+const fn first_value(s: &[u8]) -> &[u8] {
+    let s = trim(s);
+    let (quote, rest) = match s {
+        [quote @ (b'"' | b'\''), rest @ ..] => (Some(*quote), rest),
+        _ => (None, s),
+    };
+    let mut len = 0;
+    while len < rest.len() {
+        let byte = rest[len];
+        let end = match quote {
+            Some(quote) => byte == quote,
+            None => byte.is_ascii_whitespace(),
+        };
+        if end {
+            break;
+        }
+        len += 1;
+    }
+    rest.split_at(len).0
+}
+
+const fn hex_digit(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+const fn hex_byte(high: u8, low: u8) -> Option<u8> {
+    match (hex_digit(high), hex_digit(low)) {
+        (Some(high), Some(low)) => Some(high << 4 | low),
+        _ => None,
+    }
+}
+
+/// Parses `#rrggbb`, `0xrrggbb` or `rrggbb`.
+const fn parse_hex_color(s: &[u8]) -> Option<Rgb888> {
+    let digits = match s {
+        [b'#', digits @ ..] | [b'0', b'x' | b'X', digits @ ..] => digits,
+        _ => s,
+    };
+    match digits {
+        [r1, r0, g1, g0, b1, b0] => {
+            match (hex_byte(*r1, *r0), hex_byte(*g1, *g0), hex_byte(*b1, *b0)) {
+                (Some(r), Some(g), Some(b)) => Some(Rgb888::new(r, g, b)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Parses `#rrggbb` or `rgb:rr/gg/bb`.
+const fn parse_xresources_color(s: &[u8]) -> Option<Rgb888> {
+    match s {
+        [b'r', b'g', b'b', b':', r1, r0, b'/', g1, g0, b'/', b1, b0] => {
+            match (hex_byte(*r1, *r0), hex_byte(*g1, *g0), hex_byte(*b1, *b0)) {
+                (Some(r), Some(g), Some(b)) => Some(Rgb888::new(r, g, b)),
+                _ => None,
+            }
+        }
+        [b'#', ..] => parse_hex_color(s),
+        _ => None,
+    }
+}
+
+/// Returns the index of a `baseXX` key.
+const fn base16_index(key: &[u8]) -> Option<usize> {
+    match key {
+        [b'b', b'a', b's', b'e', b'0', digit] => match hex_digit(*digit) {
+            Some(index) => Some(index as usize),
+            None => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the last component of a resource name, e.g. `color1` for `URxvt*color1`.
+const fn resource_name(key: &[u8]) -> &[u8] {
+    let mut start = key.len();
+    while start > 0 && key[start - 1] != b'*' && key[start - 1] != b'.' {
+        start -= 1;
+    }
+    key.split_at(start).1
+}
+
+const fn xresources_slot(name: &[u8]) -> Option<usize> {
+    match name {
+        b"foreground" => Some(FOREGROUND),
+        b"background" => Some(BACKGROUND),
+        [b'c', b'o', b'l', b'o', b'r', digit @ b'0'..=b'9'] => Some((*digit - b'0') as usize),
+        [b'c', b'o', b'l', b'o', b'r', b'1', digit @ b'0'..=b'5'] => {
+            Some(10 + (*digit - b'0') as usize)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::pixelcolor::RgbColor;
+    use rstest::rstest;
+
+    const DEFAULT_DARK: &str = r#"
+scheme: "Default Dark"
+author: "Chris Kempson (http://chriskempson.com)"
+base00: "181818"
+base01: "282828"
+base02: "383838"
+base03: "585858"
+base04: "b8b8b8"
+base05: "d8d8d8"
+base06: "e8e8e8"
+base07: "f8f8f8"
+base08: "ab4642"
+base09: "dc9656"
+base0A: "f7ca88"
+base0B: "a1b56c"
+base0C: "86c1b9"
+base0D: "7cafc2"
+base0E: "ba8baf"
+base0F: "a16946"
+"#;
+
+    #[rstest]
+    fn parses_base16_yaml() {
+        let theme = ColorTheme::from_base16_yaml(DEFAULT_DARK).unwrap();
+        assert_eq!(theme.background, Rgb888::new(0x18, 0x18, 0x18));
+        assert_eq!(theme.black, Rgb888::new(0x18, 0x18, 0x18));
+        assert_eq!(theme.foreground, Rgb888::new(0xd8, 0xd8, 0xd8));
+        assert_eq!(theme.gray, Rgb888::new(0xd8, 0xd8, 0xd8));
+        assert_eq!(theme.dark_gray, Rgb888::new(0x58, 0x58, 0x58));
+        assert_eq!(theme.white, Rgb888::new(0xf8, 0xf8, 0xf8));
+        assert_eq!(theme.red, Rgb888::new(0xab, 0x46, 0x42));
+        assert_eq!(theme.light_red, Rgb888::new(0xab, 0x46, 0x42));
+        assert_eq!(theme.yellow, Rgb888::new(0xf7, 0xca, 0x88));
+        assert_eq!(theme.green, Rgb888::new(0xa1, 0xb5, 0x6c));
+        assert_eq!(theme.cyan, Rgb888::new(0x86, 0xc1, 0xb9));
+        assert_eq!(theme.blue, Rgb888::new(0x7c, 0xaf, 0xc2));
+        assert_eq!(theme.magenta, Rgb888::new(0xba, 0x8b, 0xaf));
+    }
+
+    #[rstest]
+    fn parses_base16_yaml_palette() {
+        let theme = ColorTheme::from_base16_yaml(
+            "system: \"base16\"\npalette:\n  base00: \"#101010\" # background\n  base08: '#ff0000'\n",
+        )
+        .unwrap();
+        assert_eq!(theme.background, Rgb888::new(0x10, 0x10, 0x10));
+        assert_eq!(theme.red, Rgb888::RED);
+        assert_eq!(theme.green, ColorTheme::ansi().green);
+    }
+
+    #[rstest]
+    fn parses_alacritty_toml() {
+        let theme = ColorTheme::from_alacritty_toml(
+            r#"
+            [window]
+            opacity = 0.9
+
+            # Colors (Tomorrow Night)
+            [colors.primary]
+            background = '#1d1f21'
+            foreground = "0xc5c8c6" # comment
+
+            [colors.cursor]
+            text = '#ff0000'
+
+            [colors.normal]
+            black = '#000000'
+            red = '#cc6666'
+            white = '#eaeaea'
+
+            [colors.bright]
+            black = '#666666'
+            red = '#d54e53'
+            white = '#ffffff'
+            "#,
+        )
+        .unwrap();
+        assert_eq!(theme.background, Rgb888::new(0x1d, 0x1f, 0x21));
+        assert_eq!(theme.foreground, Rgb888::new(0xc5, 0xc8, 0xc6));
+        assert_eq!(theme.black, Rgb888::new(0x00, 0x00, 0x00));
+        assert_eq!(theme.red, Rgb888::new(0xcc, 0x66, 0x66));
+        assert_eq!(theme.gray, Rgb888::new(0xea, 0xea, 0xea));
+        assert_eq!(theme.dark_gray, Rgb888::new(0x66, 0x66, 0x66));
+        assert_eq!(theme.light_red, Rgb888::new(0xd5, 0x4e, 0x53));
+        assert_eq!(theme.white, Rgb888::new(0xff, 0xff, 0xff));
+    }
+
+    #[rstest]
+    fn parses_xresources() {
+        let theme = ColorTheme::from_xresources(
+            "! Tomorrow Night\n#define t_background #1d1f21\n*.foreground: #c5c8c6\nURxvt*background: rgb:1d/1f/21\n*color1: #cc6666\n*.color9:  #d54e53\nXTerm.color15: #ffffff\n*.cursorColor: #c5c8c6\n",
+        )
+        .unwrap();
+        assert_eq!(theme.foreground, Rgb888::new(0xc5, 0xc8, 0xc6));
+        assert_eq!(theme.background, Rgb888::new(0x1d, 0x1f, 0x21));
+        assert_eq!(theme.red, Rgb888::new(0xcc, 0x66, 0x66));
+        assert_eq!(theme.light_red, Rgb888::new(0xd5, 0x4e, 0x53));
+        assert_eq!(theme.white, Rgb888::WHITE);
+    }
+
+    #[rstest]
+    #[case(ColorTheme::from_base16_yaml("base00: \"18181\""), 1)]
+    #[case(ColorTheme::from_alacritty_toml("[colors.normal]\nred = 'red'"), 2)]
+    #[case(ColorTheme::from_xresources("\n\n*.color3: yellow"), 3)]
+    fn reports_invalid_colors(
+        #[case] result: Result<ColorTheme, ThemeParseError>,
+        #[case] line: usize,
+    ) {
+        assert!(matches!(
+            result,
+            Err(ThemeParseError::InvalidColor { line: l }) if l == line
+        ));
+    }
+
+    #[rstest]
+    #[case(ColorTheme::from_base16_yaml("[colors.normal]\nred = '#ff0000'"))]
+    #[case(ColorTheme::from_alacritty_toml("base08: \"ff0000\""))]
+    #[case(ColorTheme::from_xresources(""))]
+    fn reports_missing_colors(#[case] result: Result<ColorTheme, ThemeParseError>) {
+        assert!(matches!(result, Err(ThemeParseError::NoColors)));
+    }
+
+    #[rstest]
+    fn parses_at_compile_time() {
+        const THEME: ColorTheme =
+            crate::color_theme!(alacritty_toml, "[colors.normal]\nblue = '#0000ff'");
+        assert_eq!(THEME.blue, Rgb888::BLUE);
+    }
+}
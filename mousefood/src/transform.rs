@@ -0,0 +1,205 @@
+//! Coordinate transformation between the terminal and the display.
+//!
+//! The terminal is laid out in a logical coordinate space which is mapped onto the physical
+//! display by [`Transform`]. This allows rotating and mirroring the terminal on display drivers
+//! that can't do it themselves.
+
+use embedded_graphics::Pixel;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Dimensions, Point, Size};
+use embedded_graphics::primitives::{PointsIter, Rectangle};
+
+use crate::backend::Rotation;
+
+/// Maps logical (terminal) coordinates onto the physical display.
+///
+/// Mirroring is applied in the logical space, before the rotation.
+#[derive(Clone, Copy)]
+pub(crate) struct Transform {
+    area: Rectangle,
+    rotation: Rotation,
+    mirror_horizontal: bool,
+    mirror_vertical: bool,
+}
+
+impl Transform {
+    pub(crate) fn new(
+        area: Rectangle,
+        rotation: Rotation,
+        mirror_horizontal: bool,
+        mirror_vertical: bool,
+    ) -> Self {
+        Self {
+            area,
+            rotation,
+            mirror_horizontal,
+            mirror_vertical,
+        }
+    }
+
+    /// Size of the logical coordinate space.
+    pub(crate) fn size(&self) -> Size {
+        match self.rotation {
+            Rotation::Rotate0 | Rotation::Rotate180 => self.area.size,
+            Rotation::Rotate90 | Rotation::Rotate270 => {
+                Size::new(self.area.size.height, self.area.size.width)
+            }
+        }
+    }
+
+    fn is_identity(&self) -> bool {
+        matches!(self.rotation, Rotation::Rotate0)
+            && !self.mirror_horizontal
+            && !self.mirror_vertical
+    }
+
+    /// Maps a logical point to a physical point.
+    pub(crate) fn map_point(&self, point: Point) -> Point {
+        let size = self.size();
+        let (w, h) = (size.width as i32, size.height as i32);
+
+        let x = if self.mirror_horizontal {
+            w - 1 - point.x
+        } else {
+            point.x
+        };
+        let y = if self.mirror_vertical {
+            h - 1 - point.y
+        } else {
+            point.y
+        };
+
+        let rotated = match self.rotation {
+            Rotation::Rotate0 => Point::new(x, y),
+            Rotation::Rotate90 => Point::new(h - 1 - y, x),
+            Rotation::Rotate180 => Point::new(w - 1 - x, h - 1 - y),
+            Rotation::Rotate270 => Point::new(y, w - 1 - x),
+        };
+
+        rotated + self.area.top_left
+    }
+
+    /// Maps a logical rectangle to a physical rectangle.
+    pub(crate) fn map_rectangle(&self, rectangle: &Rectangle) -> Rectangle {
+        match rectangle.bottom_right() {
+            None => Rectangle::new(self.map_point(rectangle.top_left), Size::zero()),
+            Some(bottom_right) => Rectangle::with_corners(
+                self.map_point(rectangle.top_left),
+                self.map_point(bottom_right),
+            ),
+        }
+    }
+}
+
+/// A [`DrawTarget`] adapter that draws in logical coordinates onto a physical target.
+///
+/// Pixels outside of the logical area are discarded.
+pub(crate) struct Transformed<'a, T> {
+    target: &'a mut T,
+    transform: Transform,
+}
+
+impl<'a, T: DrawTarget> Transformed<'a, T> {
+    pub(crate) fn new(target: &'a mut T, transform: Transform) -> Self {
+        Self { target, transform }
+    }
+}
+
+impl<T: DrawTarget> Dimensions for Transformed<'_, T> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(Point::zero(), self.transform.size())
+    }
+}
+
+impl<T: DrawTarget> DrawTarget for Transformed<'_, T> {
+    type Color = T::Color;
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bounding_box = self.bounding_box();
+        let transform = self.transform;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .filter(|Pixel(point, _)| bounding_box.contains(*point))
+                .map(|Pixel(point, color)| Pixel(transform.map_point(point), color)),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        // The pixel order is only preserved by the identity transform, and the target must not
+        // be asked to draw outside the logical area.
+        if self.transform.is_identity() && self.bounding_box().intersection(area) == *area {
+            self.target
+                .fill_contiguous(&self.transform.map_rectangle(area), colors)
+        } else {
+            self.draw_iter(
+                area.points()
+                    .zip(colors)
+                    .map(|(point, color)| Pixel(point, color)),
+            )
+        }
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = self.bounding_box().intersection(area);
+        if area.is_zero_sized() {
+            return Ok(());
+        }
+        self.target
+            .fill_solid(&self.transform.map_rectangle(&area), color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const AREA: Rectangle = Rectangle::new(Point::zero(), Size::new(4, 2));
+
+    #[rstest]
+    #[case(Rotation::Rotate0, false, false, Point::new(1, 0), Point::new(1, 0))]
+    #[case(Rotation::Rotate90, false, false, Point::new(1, 0), Point::new(3, 1))]
+    #[case(Rotation::Rotate180, false, false, Point::new(1, 0), Point::new(2, 1))]
+    #[case(Rotation::Rotate270, false, false, Point::new(1, 0), Point::new(0, 0))]
+    #[case(Rotation::Rotate0, true, false, Point::new(1, 0), Point::new(2, 0))]
+    #[case(Rotation::Rotate0, false, true, Point::new(1, 0), Point::new(1, 1))]
+    #[case(Rotation::Rotate90, true, false, Point::new(0, 1), Point::new(2, 1))]
+    fn maps_points(
+        #[case] rotation: Rotation,
+        #[case] mirror_horizontal: bool,
+        #[case] mirror_vertical: bool,
+        #[case] logical: Point,
+        #[case] physical: Point,
+    ) {
+        let transform = Transform::new(AREA, rotation, mirror_horizontal, mirror_vertical);
+        assert_eq!(transform.map_point(logical), physical);
+    }
+
+    #[rstest]
+    #[case(Rotation::Rotate0, Size::new(4, 2))]
+    #[case(Rotation::Rotate90, Size::new(2, 4))]
+    #[case(Rotation::Rotate180, Size::new(4, 2))]
+    #[case(Rotation::Rotate270, Size::new(2, 4))]
+    fn swaps_size_when_rotated(#[case] rotation: Rotation, #[case] size: Size) {
+        assert_eq!(Transform::new(AREA, rotation, false, false).size(), size);
+    }
+
+    #[rstest]
+    #[case(Rotation::Rotate0)]
+    #[case(Rotation::Rotate90)]
+    #[case(Rotation::Rotate180)]
+    #[case(Rotation::Rotate270)]
+    fn maps_rectangles_onto_area(#[case] rotation: Rotation) {
+        let transform = Transform::new(AREA, rotation, true, false);
+        let logical = Rectangle::new(Point::zero(), transform.size());
+        assert_eq!(transform.map_rectangle(&logical), AREA);
+    }
+}